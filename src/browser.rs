@@ -0,0 +1,106 @@
+/*
+    This file is a part of bookman software.
+
+    This module contains logic required to open bookmarks in a browser.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use crate::config;
+use crate::entry::Bookmark;
+use crate::error::{BookmanError, Result};
+use crate::query::Query;
+
+use std::env;
+use std::process::{Command, Stdio};
+
+#[cfg(target_os = "macos")]
+const DEFAULT_BROWSER: &str = "open";
+
+#[cfg(not(target_os = "macos"))]
+const DEFAULT_BROWSER: &str = "xdg-open";
+
+pub fn open(bookmark: &Bookmark) -> Result<()> {
+    let command = browser_for(bookmark);
+
+    let mut parts: Vec<String> = command.split_whitespace().map(|p| p.to_string()).collect();
+    if parts.is_empty() {
+        return Err(BookmanError::External("browser command is empty".to_string()));
+    }
+
+    if parts.iter().any(|p| p.contains("{}")) {
+        parts = parts.iter().map(|p| p.replace("{}", &bookmark.url)).collect();
+    } else {
+        parts.push(bookmark.url.clone());
+    }
+
+    Command::new(&parts[0])
+        .args(&parts[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| BookmanError::External(format!("failed to run '{}': {}", parts[0], err)))?;
+
+    Ok(())
+}
+
+fn browser_for(bookmark: &Bookmark) -> String {
+    if let Some(command) = matching_rule(config::BROWSER_RULES, bookmark) {
+        return command.to_string();
+    }
+
+    if !config::BROWSER.is_empty() {
+        return config::BROWSER.to_string();
+    }
+
+    // $BROWSER may hold a colon separated list of browsers, use the first one.
+    if let Ok(browser) = env::var("BROWSER")
+        && let Some(first) = browser.split(':').find(|b| !b.trim().is_empty())
+    {
+        return first.to_string();
+    }
+
+    DEFAULT_BROWSER.to_string()
+}
+
+// Rules are queries, so "tag:work" or "domain:github.com" match as they do in `list`.
+fn matching_rule<'a>(rules: &[(&str, &'a str)], bookmark: &Bookmark) -> Option<&'a str> {
+    rules.iter()
+        .find(|(rule, _)| Query::parse(rule).is_ok_and(|query| query.matches(bookmark)))
+        .map(|(_, command)| *command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_first_matching_rule() {
+        let rules = [("tag:work", "firefox -P work"), ("domain:github.com", "chromium"), ("tag:\"to read", "broken")];
+        let bookmark = |url: &str, tags: &[&str]| Bookmark {
+            url: url.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(matching_rule(&rules, &bookmark("https://github.com/a", &["work"])), Some("firefox -P work"));
+        assert_eq!(matching_rule(&rules, &bookmark("https://gist.github.com/b", &[])), Some("chromium"));
+        assert_eq!(matching_rule(&rules, &bookmark("https://example.com/", &["to read"])), None);
+    }
+}
@@ -0,0 +1,524 @@
+/*
+    This file is a part of bookman software.
+
+    It contains the clipboard providers: wl-clipboard on Wayland, arboard on
+    X11 (and macOS/Windows), OSC 52 escape sequences for SSH sessions and tmux
+    buffers. The provider is picked by config::CLIPBOARD.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use std::env;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+use crate::config;
+use crate::error::{BookmanError, Result};
+use crate::utils;
+
+pub trait ClipboardProvider {
+    fn name(&self) -> &'static str;
+    fn copy(&self, text: &str) -> Result<()>;
+    fn paste(&self) -> Result<String>;
+
+    /// Whether `paste` works. Copies to a write-only clipboard aren't held or cleared,
+    /// bookman couldn't tell whether the text is still there.
+    fn readable(&self) -> bool {
+        true
+    }
+
+    /// Copies HTML as rich text, with `alt` for apps that only take plain text, where
+    /// supported and the markup itself otherwise.
+    fn copy_html(&self, html: &str, _alt: &str) -> Result<()> {
+        self.copy(html)
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.copy("")
+    }
+
+    /// Copies `text`, or `html` with `text` as its plain version. Where the copy would
+    /// go away with bookman, it's served until something else is copied.
+    fn keep(&self, text: &str, html: Option<&str>) -> Result<()> {
+        match html {
+            Some(html) => self.copy_html(html, text),
+            None => self.copy(text),
+        }
+    }
+
+    /// Copies `text`, or `html` with `text` as its plain version, and returns after
+    /// `seconds`, or earlier once something else is copied.
+    fn hold(&self, text: &str, html: Option<&str>, seconds: u64) -> Result<()> {
+        if let Some(html) = html {
+            self.copy_html(html, text)?;
+        } else {
+            self.copy(text)?;
+        }
+        utils::sleep(seconds);
+
+        Ok(())
+    }
+}
+
+/// wl-copy and wl-paste, the text is written to wl-copy's stdin.
+pub struct Wayland {
+    /// Also copy to the primary selection.
+    pub primary: bool,
+}
+
+/// arboard, used on X11 and on macOS and Windows.
+pub struct X11 {
+    /// Also copy to the PRIMARY selection.
+    pub primary: bool,
+}
+
+/// OSC 52 escape sequence written to the terminal, works over SSH.
+pub struct Osc52;
+
+/// The tmux paste buffer, also passed on to the outer terminal by tmux 3.2+.
+pub struct Tmux;
+
+// Runs a program with `text` on its stdin and returns whether it succeeded.
+fn pipe_to(program: &str, args: &[&str], text: &str) -> Result<bool> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| BookmanError::Clipboard(format!("failed to run {}: {}", program, err)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+
+    Ok(child.wait()?.success())
+}
+
+fn read_from(program: &str, args: &[&str]) -> Result<Option<String>> {
+    let output = Command::new(program)
+        .args(args)
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|err| BookmanError::Clipboard(format!("failed to run {}: {}", program, err)))?;
+
+    Ok(output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+impl ClipboardProvider for Wayland {
+    fn name(&self) -> &'static str {
+        "wayland"
+    }
+
+    fn copy(&self, text: &str) -> Result<()> {
+        // wl-copy forks and keeps serving the clipboard on its own.
+        if !pipe_to("wl-copy", &[], text)? {
+            return Err(BookmanError::Clipboard("wl-copy failed".to_string()));
+        }
+        if self.primary && !pipe_to("wl-copy", &["--primary"], text)? {
+            return Err(BookmanError::Clipboard("wl-copy --primary failed".to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn copy_html(&self, html: &str, alt: &str) -> Result<()> {
+        // wl-copy offers a single type, wl-clipboard-rs (through arboard) offers text/plain as well.
+        Ok(arboard::Clipboard::new()?.set_html(html, Some(alt))?)
+    }
+
+    fn paste(&self) -> Result<String> {
+        // wl-paste fails when nothing is copied.
+        Ok(read_from("wl-paste", &["--no-newline"])?.unwrap_or_default())
+    }
+
+    fn clear(&self) -> Result<()> {
+        pipe_to("wl-copy", &["--clear"], "")?;
+        if self.primary {
+            pipe_to("wl-copy", &["--primary", "--clear"], "")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ClipboardProvider for X11 {
+    fn name(&self) -> &'static str {
+        "x11"
+    }
+
+    fn copy(&self, text: &str) -> Result<()> {
+        self.persist(text, None)
+    }
+
+    fn copy_html(&self, html: &str, alt: &str) -> Result<()> {
+        self.persist(alt, Some(html))
+    }
+
+    fn paste(&self) -> Result<String> {
+        Ok(arboard::Clipboard::new()?.get_text().unwrap_or_default())
+    }
+
+    fn clear(&self) -> Result<()> {
+        Ok(arboard::Clipboard::new()?.clear()?)
+    }
+
+    fn keep(&self, text: &str, html: Option<&str>) -> Result<()> {
+        self.serve(text, html, None)
+    }
+
+    fn hold(&self, text: &str, html: Option<&str>, seconds: u64) -> Result<()> {
+        self.serve(text, html, Some(Instant::now() + Duration::from_secs(seconds)))
+    }
+}
+
+impl X11 {
+    // Without a clipboard manager an X11 selection goes away with the process serving it,
+    // so a `bookman hold-clipboard --keep` process serves it until something else is copied.
+    fn persist(&self, text: &str, html: Option<&str>) -> Result<()> {
+        if cfg!(target_os = "linux") {
+            // Fails here rather than in the background without a display.
+            arboard::Clipboard::new()?;
+            spawn_helper(text, html, true)
+        } else {
+            self.serve(text, html, None)
+        }
+    }
+
+    // Copies and waits until `deadline`, or until something else is copied without one.
+    fn serve(&self, text: &str, html: Option<&str>, deadline: Option<Instant>) -> Result<()> {
+        let mut clipboard = arboard::Clipboard::new()?;
+
+        #[cfg(target_os = "linux")]
+        {
+            use arboard::{LinuxClipboardKind, SetExtLinux};
+
+            if self.primary {
+                clipboard.set().clipboard(LinuxClipboardKind::Primary).text(text)?;
+            }
+            // The selection is served by this process, so it has to stay alive.
+            let set = match deadline {
+                Some(deadline) => clipboard.set().wait_until(deadline),
+                None => clipboard.set().wait(),
+            };
+            if let Some(html) = html {
+                set.html(html, Some(text))?;
+            } else {
+                set.text(text)?;
+            }
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            if let Some(html) = html {
+                clipboard.set_html(html, Some(text))?;
+            } else {
+                clipboard.set_text(text)?;
+            }
+            if let Some(deadline) = deadline {
+                std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The escape sequence that sets the clipboard, wrapped for tmux when running inside it.
+pub fn osc52_sequence(text: &str, tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+
+    if tmux {
+        format!("\x1bPtmux;\x1b{}\x1b\\", sequence)
+    } else {
+        sequence
+    }
+}
+
+impl ClipboardProvider for Osc52 {
+    fn name(&self) -> &'static str {
+        "osc52"
+    }
+
+    fn copy(&self, text: &str) -> Result<()> {
+        let mut tty = OpenOptions::new()
+            .write(true)
+            .open("/dev/tty")
+            .map_err(|err| BookmanError::Clipboard(format!("can't open the terminal: {}", err)))?;
+        tty.write_all(osc52_sequence(text, env::var_os("TMUX").is_some()).as_bytes())?;
+
+        Ok(())
+    }
+
+    fn paste(&self) -> Result<String> {
+        Err(BookmanError::Clipboard("the osc52 clipboard can't be read".to_string()))
+    }
+
+    fn readable(&self) -> bool {
+        false
+    }
+}
+
+impl ClipboardProvider for Tmux {
+    fn name(&self) -> &'static str {
+        "tmux"
+    }
+
+    fn copy(&self, text: &str) -> Result<()> {
+        // -w also sets the outer clipboard but only exists since tmux 3.2.
+        if pipe_to("tmux", &["load-buffer", "-w", "-"], text)? || pipe_to("tmux", &["load-buffer", "-"], text)? {
+            Ok(())
+        } else {
+            Err(BookmanError::Clipboard("tmux load-buffer failed".to_string()))
+        }
+    }
+
+    fn paste(&self) -> Result<String> {
+        Ok(read_from("tmux", &["save-buffer", "-"])?.unwrap_or_default())
+    }
+
+    fn clear(&self) -> Result<()> {
+        read_from("tmux", &["delete-buffer"])?;
+
+        Ok(())
+    }
+}
+
+/// Picks a backend from the session: `has_var` tells whether an environment
+/// variable is set and `installed` whether a program is available.
+pub fn detect(has_var: impl Fn(&str) -> bool, installed: impl Fn(&str) -> bool) -> &'static str {
+    let graphical = has_var("WAYLAND_DISPLAY") || has_var("DISPLAY");
+    let ssh = has_var("SSH_TTY") || has_var("SSH_CONNECTION");
+
+    if ssh && !graphical && has_var("TMUX") {
+        "tmux"
+    } else if ssh && !graphical {
+        "osc52"
+    } else if has_var("WAYLAND_DISPLAY") && installed("wl-copy") {
+        "wayland"
+    } else if !graphical && has_var("TMUX") {
+        "tmux"
+    } else {
+        "x11"
+    }
+}
+
+/// The provider selected by config::CLIPBOARD.
+pub fn provider() -> Result<Box<dyn ClipboardProvider>> {
+    let backend = match config::CLIPBOARD {
+        "auto" => detect(|var| env::var_os(var).is_some_and(|value| !value.is_empty()), utils::is_installed),
+        backend => backend,
+    };
+    let primary = config::CLIPBOARD_PRIMARY;
+
+    match backend {
+        "wayland" => Ok(Box::new(Wayland { primary })),
+        "x11" => Ok(Box::new(X11 { primary })),
+        "osc52" => Ok(Box::new(Osc52)),
+        "tmux" => Ok(Box::new(Tmux)),
+        other => Err(BookmanError::InvalidInput(format!("unknown clipboard backend '{}'", other))),
+    }
+}
+
+/// Copies `text` and clears it after config::CLIPBOARD_HOLD_SECONDS, unless
+/// something else was copied in the meantime. With `html` the text is its plain
+/// version and the HTML is copied as rich text on Wayland and X11, as markup
+/// by the other backends.
+///
+/// Wayland, X11 and tmux wait out the hold before clearing (or restoring) the
+/// clipboard. OSC 52 can't be read back, so it returns right away and leaves
+/// the text in the clipboard. X11 copies that outlive bookman, without a hold
+/// or when restoring, are served by a `bookman hold-clipboard --keep` process.
+pub fn copy(text: &str, html: Option<&str>) -> Result<()> {
+    let provider = provider()?;
+
+    #[cfg(debug_assertions)]
+    {
+        println!("Copied to clipboard ({}): {}", provider.name(), text);
+    }
+
+    copy_with(provider.as_ref(), text, html, config::CLIPBOARD_HOLD_SECONDS, config::CLIPBOARD_RESTORE)
+}
+
+fn copy_with(provider: &dyn ClipboardProvider, text: &str, html: Option<&str>, seconds: u64, restore: bool)
+    -> Result<()> {
+    if seconds == 0 || !provider.readable() {
+        return match html {
+            Some(html) => provider.copy_html(html, text),
+            None => provider.copy(text),
+        };
+    }
+
+    let previous = if restore {
+        provider.paste().ok().filter(|previous| !previous.is_empty() && previous != text)
+    } else {
+        None
+    };
+    provider.hold(text, html, seconds)?;
+
+    // A clipboard that can't be read is left alone, it may hold something else by now.
+    match provider.paste() {
+        Ok(current) if current == text || html == Some(current.as_str()) => match previous {
+            Some(previous) => provider.copy(&previous),
+            None => provider.clear(),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Like `copy` but returns right away, the clipboard is held and cleared by
+/// a `bookman hold-clipboard` process running in the background.
+pub fn copy_in_background(text: &str, html: Option<&str>) -> Result<()> {
+    if config::CLIPBOARD_HOLD_SECONDS == 0 || !provider()?.readable() {
+        return copy(text, html);
+    }
+
+    spawn_helper(text, html, false)
+}
+
+/// Keeps `text`, or `html` with `text` as its plain version, in the clipboard until
+/// something else is copied, run by `bookman hold-clipboard --keep`.
+pub fn keep(text: &str, html: Option<&str>) -> Result<()> {
+    provider()?.keep(text, html)
+}
+
+// Runs `bookman hold-clipboard` in the background, with `keep` it serves the copy
+// until it's replaced, otherwise it's held and cleared like `copy` does.
+fn spawn_helper(text: &str, html: Option<&str>, keep: bool) -> Result<()> {
+    let mut helper = Command::new(env::current_exe()?)
+        .arg("hold-clipboard")
+        .args(html.is_some().then_some("--html"))
+        .args(keep.then_some("--keep"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // The helper reads the text and the HTML separated by a NUL.
+    if let Some(mut stdin) = helper.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+        if let Some(html) = html {
+            stdin.write_all(format!("\0{}", html).as_bytes())?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn paste() -> Result<String> {
+    let provider = provider()?;
+    let text = provider.paste()?;
+
+    #[cfg(debug_assertions)]
+    {
+        println!("Copied from clipboard ({}): {}", provider.name(), text);
+    }
+
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn detect_with(vars: &[&str], installed: &[&str]) -> &'static str {
+        detect(|var| vars.contains(&var), |program| installed.contains(&program))
+    }
+
+    #[test]
+    fn detects_the_session() {
+        assert_eq!(detect_with(&["WAYLAND_DISPLAY"], &["wl-copy"]), "wayland");
+        assert_eq!(detect_with(&["WAYLAND_DISPLAY"], &[]), "x11");
+        assert_eq!(detect_with(&["DISPLAY"], &["wl-copy"]), "x11");
+        assert_eq!(detect_with(&["SSH_TTY"], &[]), "osc52");
+        assert_eq!(detect_with(&["SSH_CONNECTION", "TMUX"], &[]), "tmux");
+        assert_eq!(detect_with(&["SSH_TTY", "DISPLAY"], &[]), "x11");
+        assert_eq!(detect_with(&["TMUX"], &[]), "tmux");
+    }
+
+    // A clipboard in memory, `replaced_by` is copied by someone else while bookman holds.
+    struct Fake {
+        clipboard: RefCell<String>,
+        readable: bool,
+        replaced_by: Option<&'static str>,
+    }
+
+    impl Fake {
+        fn new(clipboard: &str, readable: bool, replaced_by: Option<&'static str>) -> Fake {
+            Fake { clipboard: RefCell::new(clipboard.to_string()), readable, replaced_by }
+        }
+    }
+
+    impl ClipboardProvider for Fake {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        fn copy(&self, text: &str) -> Result<()> {
+            self.clipboard.replace(text.to_string());
+            Ok(())
+        }
+
+        fn paste(&self) -> Result<String> {
+            if self.readable {
+                Ok(self.clipboard.borrow().clone())
+            } else {
+                Err(BookmanError::Clipboard("write-only".to_string()))
+            }
+        }
+
+        fn readable(&self) -> bool {
+            self.readable
+        }
+
+        fn hold(&self, text: &str, html: Option<&str>, _seconds: u64) -> Result<()> {
+            self.keep(text, html)?;
+            if let Some(other) = self.replaced_by {
+                self.copy(other)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn copied(fake: Fake, html: Option<&str>, seconds: u64, restore: bool) -> String {
+        copy_with(&fake, "https://a.b/", html, seconds, restore).unwrap();
+        fake.clipboard.into_inner()
+    }
+
+    #[test]
+    fn clears_or_restores_after_holding() {
+        assert_eq!(copied(Fake::new("old", true, None), None, 0, true), "https://a.b/");
+        assert_eq!(copied(Fake::new("old", true, None), None, 45, false), "");
+        assert_eq!(copied(Fake::new("old", true, None), None, 45, true), "old");
+        assert_eq!(copied(Fake::new("old", true, None), Some("<a>"), 45, true), "old");
+        assert_eq!(copied(Fake::new("old", true, Some("mine")), None, 45, true), "mine");
+        assert_eq!(copied(Fake::new("old", false, None), None, 45, true), "https://a.b/");
+    }
+
+    #[test]
+    fn encodes_osc52() {
+        assert_eq!(osc52_sequence("https://a.b/?q='x'", false), "\x1b]52;c;aHR0cHM6Ly9hLmIvP3E9J3gn\x07");
+        assert_eq!(osc52_sequence("hi", true), "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\");
+    }
+}
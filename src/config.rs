@@ -1,9 +1,41 @@
 /*
     This file is a part of bookman software.
 
-    It contains two configuration variables:
+    It contains the configuration variables:
         DB_FILE: path to the database file with bookmarks.
         DB_PASS: a secret passphrase used to encrypt/open the database.
+        URL_SCHEMES: URL schemes accepted when adding or editing bookmarks.
+        BROWSER: command used to open bookmarks, falls back to $BROWSER and xdg-open.
+        BROWSER_RULES: browser commands for bookmarks matching a query, e.g. per tag
+            ("tag:work") or per domain ("domain:github.com"), the first matching rule wins. A "{}" in the command
+            is replaced with the URL, otherwise the URL is appended.
+        DISPLAY_TEMPLATE: how bookmarks are shown in the picker. Supported fields
+            are {id}, {name}, {url}, {description}, {tags}, {folder}, {visits} and {state}.
+        PICKER: menu used by search, open, clip and remove: "skim" (built in, needs a
+            terminal), "dmenu", "rofi", "fuzzel", "wofi" or "fzf". --picker overrides it.
+        TRASH_RETENTION_DAYS: removed bookmarks are kept in the trash for this many
+            days before they are deleted for good.
+        CLIPBOARD: clipboard backend, "wayland" (wl-clipboard), "x11" (also used on
+            macOS and Windows), "osc52" (terminal escape sequence, works over SSH),
+            "tmux" (tmux buffer) or "auto" to pick one from the session.
+        CLIPBOARD_PRIMARY: also copy to the PRIMARY selection on X11 and Wayland.
+        CLIPBOARD_HOLD_SECONDS: copied URLs are cleared from the clipboard after this
+            many seconds unless something else was copied meanwhile, 0 keeps them.
+            "osc52" can't read the clipboard back, so its copies are always kept.
+        CLIPBOARD_RESTORE: put the previous clipboard contents back instead of clearing it.
+        CLIP_FORMAT: how `clip` copies bookmarks unless --as is given, one of "plain",
+            "markdown", "html", "org", "rst" or "title".
+        NOTIFY: where notifications go, "desktop", "stderr" or "none". Desktop
+            notifications that can't be shown (no D-Bus, SSH) go to stderr.
+        NOTIFY_EVENTS: events that are notified: "saved", "updated", "copied",
+            "removed" and "queue" (sent by `queue --notify`).
+        NOTIFY_URGENCY: urgency of desktop notifications, "low", "normal" or "critical".
+        NOTIFY_TIMEOUT_MS: how long desktop notifications are shown, -1 leaves it to
+            the notification daemon and 0 keeps them until they are dismissed.
+        NOTIFY_ACTIONS: offer Open, Edit and Undo buttons when a bookmark is saved with
+            `add --clipboard` (Linux). bookman waits until the notification is closed.
+        TERMINAL: terminal emulator used to run $EDITOR when bookman isn't started
+            from a terminal, e.g. "foot" or "alacritty -e". It has to wait for the editor.
 
     Copyright (c) 2025 Pavel Pleskunov.
 
@@ -24,3 +56,23 @@
 */
 pub const DB_FILE: &str = ".local/share/bookman/bookmarks.db";
 pub const DB_PASS: &str = "aes256:mysupersecretkey";
+pub const URL_SCHEMES: &[&str] = &["http", "https", "file", "ftp", "mailto"];
+pub const BROWSER: &str = "";
+pub const BROWSER_RULES: &[(&str, &str)] = &[
+    // ("tag:work", "firefox -P work"),
+    // ("domain:github.com", "chromium --profile-directory=Work"),
+];
+pub const DISPLAY_TEMPLATE: &str = "{id} | {name} | {url}";
+pub const PICKER: &str = "skim";
+pub const TRASH_RETENTION_DAYS: i64 = 30;
+pub const CLIPBOARD: &str = "auto";
+pub const CLIPBOARD_PRIMARY: bool = false;
+pub const CLIPBOARD_HOLD_SECONDS: u64 = 45;
+pub const CLIPBOARD_RESTORE: bool = false;
+pub const CLIP_FORMAT: &str = "plain";
+pub const NOTIFY: &str = "desktop";
+pub const NOTIFY_EVENTS: &[&str] = &["saved", "updated", "copied", "removed", "queue"];
+pub const NOTIFY_URGENCY: &str = "normal";
+pub const NOTIFY_TIMEOUT_MS: i32 = -1;
+pub const NOTIFY_ACTIONS: bool = true;
+pub const TERMINAL: &str = "";
@@ -21,11 +21,30 @@
     USA
 */
 
-use crate::entry::Bookmark;
-use rusqlite::{params, Connection};
-use std::error::Error;
+use crate::entry::{Bookmark, Change, State};
+use crate::error::{BookmanError, Result};
+use crate::query::Query;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{params, params_from_iter, Connection, Row};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
 
-pub fn new_table(conn: &Connection) -> Result<(), Box<dyn Error>> {
+const SELECT_BOOKMARKS: &str =
+    "SELECT b.id, b.name, b.url, b.description, GROUP_CONCAT(t.name, ','), b.folder,
+            b.visits, b.last_visited, b.added, b.link_status, b.snippet,
+            (SELECT SUM(CASE e.kind WHEN 'open' THEN 1.0 ELSE 0.5 END *
+                CASE
+                    WHEN e.at > strftime('%s', 'now', '-4 days') THEN 100
+                    WHEN e.at > strftime('%s', 'now', '-14 days') THEN 70
+                    WHEN e.at > strftime('%s', 'now', '-31 days') THEN 50
+                    WHEN e.at > strftime('%s', 'now', '-90 days') THEN 30
+                    ELSE 10
+                END) FROM events e WHERE e.bookmark_id = b.id),
+            b.state, b.starred, b.rating, b.deleted_at
+     FROM bookmarks b LEFT JOIN tags t ON t.bookmark_id = b.id";
+
+pub fn new_table(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS bookmarks (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -36,66 +55,473 @@ pub fn new_table(conn: &Connection) -> Result<(), Box<dyn Error>> {
         [],
     )?;
 
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tags (
+            bookmark_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            PRIMARY KEY (bookmark_id, name)
+        );",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS events (
+            bookmark_id INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            at INTEGER NOT NULL
+        );",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS collections (
+            name TEXT PRIMARY KEY,
+            query TEXT NOT NULL
+        );",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            batch INTEGER NOT NULL,
+            op TEXT NOT NULL,
+            bookmark_id INTEGER NOT NULL,
+            before TEXT,
+            after TEXT,
+            at INTEGER NOT NULL,
+            undoes INTEGER
+        );",
+        [],
+    )?;
+
+    add_column(conn, "bookmarks", "folder", "TEXT")?;
+    add_column(conn, "bookmarks", "visits", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column(conn, "bookmarks", "last_visited", "INTEGER")?;
+    add_column(conn, "bookmarks", "added", "INTEGER")?;
+    add_column(conn, "bookmarks", "link_status", "INTEGER")?;
+    add_column(conn, "bookmarks", "snippet", "TEXT")?;
+    add_column(conn, "bookmarks", "state", "TEXT NOT NULL DEFAULT 'read'")?;
+    add_column(conn, "bookmarks", "starred", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column(conn, "bookmarks", "rating", "INTEGER")?;
+    add_column(conn, "bookmarks", "deleted_at", "INTEGER")?;
+    add_column(conn, "history", "undoes", "INTEGER")?;
+
     Ok(())
 }
 
-pub fn insert_entry(conn: &Connection, name: &String, url: &String, description: &String) -> Result<(), Box<dyn Error>> {
-    conn.execute("INSERT INTO bookmarks (name, url, description) VALUES (?1, ?2, ?3)",
-        params![name.trim(), url.trim(), description.trim()],)?;
+pub fn register_functions(conn: &Connection) -> Result<()> {
+    conn.create_scalar_function("url_host", 1, FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC, |ctx| {
+        let url = ctx.get::<Option<String>>(0)?.unwrap_or_default();
+        Ok(Url::parse(&url).ok().and_then(|u| u.host_str().map(|h| h.to_lowercase())))
+    })?;
+
+    Ok(())
+}
+
+// Databases created by older versions lack the newer columns, so add them on demand.
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt.query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(rusqlite::Result::ok)
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
 
     Ok(())
 }
 
-pub fn get_entry(conn: &Connection, id: i32) -> Result::<Bookmark, Box<dyn Error>> {
-    let mut stmt = conn.prepare("SELECT id, name, url, description FROM bookmarks WHERE id = ?1")?;
+fn to_bookmark(row: &Row) -> rusqlite::Result<Bookmark> {
+    Ok(Bookmark {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        url: row.get(2)?,
+        description: row.get(3)?,
+        tags: split_tags(row.get(4)?),
+        folder: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+        visits: row.get(6)?,
+        last_visited: row.get(7)?,
+        added: row.get(8)?,
+        link_status: row.get(9)?,
+        snippet: row.get::<_, Option<String>>(10)?.unwrap_or_default(),
+        frecency: row.get::<_, Option<f64>>(11)?.unwrap_or_default(),
+        state: State::parse(&row.get::<_, String>(12)?).unwrap_or_default(),
+        starred: row.get(13)?,
+        rating: row.get(14)?,
+        deleted_at: row.get(15)?,
+    })
+}
+
+fn split_tags(tags: Option<String>) -> Vec<String> {
+    match tags {
+        Some(tags) => {
+            let mut tags: Vec<String> = tags.split(',').map(|t| t.to_string()).collect();
+            tags.sort();
+            tags
+        }
+        None => Vec::new(),
+    }
+}
+
+pub fn insert_entry(conn: &Connection, bookmark: &Bookmark) -> Result<i32> {
+    conn.execute(
+        "INSERT INTO bookmarks (name, url, description, folder, added, link_status, snippet, state, starred, rating)
+         VALUES (?1, ?2, ?3, ?4, COALESCE(?5, strftime('%s', 'now')), ?6, ?7, ?8, ?9, ?10)",
+        params![bookmark.name.trim(), bookmark.url.trim(), bookmark.description.trim(), bookmark.folder.trim(),
+            bookmark.added, bookmark.link_status, bookmark.snippet, bookmark.state.as_str(),
+            bookmark.starred, bookmark.rating],)?;
+
+    let id = conn.last_insert_rowid() as i32;
+    write_tags(conn, id, &bookmark.tags)?;
+    record_change(conn, "insert", id, None, snapshot(conn, id)?.as_ref())?;
+
+    Ok(id)
+}
+
+pub fn set_tags(conn: &Connection, id: i32, tags: &[String]) -> Result<()> {
+    let before = snapshot(conn, id)?;
+    write_tags(conn, id, tags)?;
+    record_change(conn, "update", id, before.as_ref(), snapshot(conn, id)?.as_ref())
+}
+
+fn write_tags(conn: &Connection, id: i32, tags: &[String]) -> Result<()> {
+    conn.execute("DELETE FROM tags WHERE bookmark_id = ?1", params![id])?;
+
+    for tag in tags {
+        conn.execute("INSERT OR IGNORE INTO tags (bookmark_id, name) VALUES (?1, ?2)",
+            params![id, tag],)?;
+    }
+
+    Ok(())
+}
+
+// Unlike `get_entry` this also finds bookmarks in the trash.
+fn snapshot(conn: &Connection, id: i32) -> Result<Option<Bookmark>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE b.id = ?1 GROUP BY b.id", SELECT_BOOKMARKS))?;
 
     let mut query = stmt.query(params![id])?;
     if let Some(row) = query.next()? {
-        return Ok(Bookmark {
-            id: row.get(0)?, 
-            name: row.get(1)?, 
-            url: row.get(2)?, 
-            description: row.get(3)?,
-        });
+        return Ok(Some(to_bookmark(row)?));
     }
 
-    Err(Box::new(rusqlite::Error::QueryReturnedNoRows))
+    Ok(None)
+}
+
+pub fn get_entry(conn: &Connection, id: i32) -> Result<Bookmark> {
+    let mut stmt = conn.prepare(&format!("{} WHERE b.id = ?1 AND b.deleted_at IS NULL GROUP BY b.id", SELECT_BOOKMARKS))?;
+
+    let mut query = stmt.query(params![id])?;
+    if let Some(row) = query.next()? {
+        return Ok(to_bookmark(row)?);
+    }
+
+    Err(BookmanError::NotFound(format!("bookmark {} not found", id)))
+}
+
+pub fn get_iterator(conn: &Connection) -> Result<Vec<Bookmark>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE b.deleted_at IS NULL GROUP BY b.id", SELECT_BOOKMARKS))?;
+    let bookmarks: Vec<_> = stmt.query_map([], to_bookmark)?.filter_map(rusqlite::Result::ok).collect();
+
+    Ok(bookmarks)
 }
 
-pub fn get_iterator(conn: &Connection) -> Result<Vec<Bookmark>, Box<dyn Error>> {
+pub fn get_filtered(conn: &Connection, query: &Query) -> Result<Vec<Bookmark>> {
+    let (condition, values) = query.to_sql();
 
-    //let mut stmt = conn.prepare("SELECT url FROM bookmarks").unwrap();
-    //let urls: Vec<String> = stmt.query_map([], |row| row.get(0)).unwrap().filter_map(Result::ok).collect();
+    let mut stmt = conn.prepare(&format!("{} WHERE b.deleted_at IS NULL AND {} GROUP BY b.id", SELECT_BOOKMARKS, condition))?;
+    let bookmarks: Vec<_> = stmt.query_map(params_from_iter(values), to_bookmark)?.filter_map(rusqlite::Result::ok).collect();
 
-    let mut stmt = conn.prepare("SELECT id, name, url, description FROM bookmarks")?;
-    let bookmarks: Vec<_> = stmt.query_map([], |row| {
-        Ok(Bookmark {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            url: row.get(2)?,
-            description: row.get(3)?,
-        })
-    })?.filter_map(Result::ok).collect();
+    Ok(bookmarks)
+}
 
-    return Ok(bookmarks);
+pub fn update_entry(conn: &Connection, bookmark: &Bookmark) -> Result<()> {
+    let before = snapshot(conn, bookmark.id)?;
+    write_entry(conn, bookmark)?;
+    record_change(conn, "update", bookmark.id, before.as_ref(), snapshot(conn, bookmark.id)?.as_ref())
 }
 
-pub fn update_entry(conn: &Connection, id: i32, name: &String, url: &String, description: &String) -> Result<(), Box<dyn Error>> {
-    conn.execute("UPDATE bookmarks SET name = ?1, url = ?2, description = ?3 WHERE id = ?4",
-        params![name.trim(), url.trim(), description.trim(), id],)?;
+fn write_entry(conn: &Connection, bookmark: &Bookmark) -> Result<()> {
+    conn.execute(
+        "UPDATE bookmarks SET name = ?1, url = ?2, description = ?3, folder = ?4, state = ?5, starred = ?6, rating = ?7
+         WHERE id = ?8",
+        params![bookmark.name.trim(), bookmark.url.trim(), bookmark.description.trim(), bookmark.folder.trim(),
+            bookmark.state.as_str(), bookmark.starred, bookmark.rating, bookmark.id],)?;
+    write_tags(conn, bookmark.id, &bookmark.tags)?;
 
     Ok(())
 }
 
-pub fn remove_entry(conn: &Connection, id: i32) -> Result<(), Box<dyn Error>> {
-    conn.execute("DELETE FROM bookmarks WHERE id = ?1", params![id])?;
+pub fn set_state(conn: &Connection, id: i32, state: State) -> Result<()> {
+    let before = snapshot(conn, id)?;
+    conn.execute("UPDATE bookmarks SET state = ?1 WHERE id = ?2", params![state.as_str(), id])?;
+    record_change(conn, "update", id, before.as_ref(), snapshot(conn, id)?.as_ref())
+}
+
+pub fn record_visit(conn: &Connection, id: i32) -> Result<()> {
+    conn.execute("UPDATE bookmarks SET visits = visits + 1, last_visited = strftime('%s', 'now') WHERE id = ?1",
+        params![id])?;
+    record_event(conn, id, "open")?;
 
     Ok(())
 }
 
-/*
-pub fn last_id(conn: &Connection) -> Option<i64> {
-    conn.query_row("SELECT MAX(id) FROM bookmarks", [], |row| row.get(0))
-        .ok()
+/// Events ("open", "copy") feed the frecency score of a bookmark.
+pub fn record_event(conn: &Connection, id: i32, kind: &str) -> Result<()> {
+    conn.execute("INSERT INTO events (bookmark_id, kind, at) VALUES (?1, ?2, strftime('%s', 'now'))",
+        params![id, kind])?;
+
+    Ok(())
+}
+
+/// Moves the bookmark to the trash, it is deleted for good by `empty_trash` or `purge_trash`.
+pub fn remove_entry(conn: &Connection, id: i32) -> Result<usize> {
+    let before = snapshot(conn, id)?;
+    let count = conn.execute("UPDATE bookmarks SET deleted_at = strftime('%s', 'now') WHERE id = ?1 AND deleted_at IS NULL",
+        params![id])?;
+
+    if count > 0 {
+        record_change(conn, "delete", id, before.as_ref(), snapshot(conn, id)?.as_ref())?;
+    }
+
+    Ok(count)
+}
+
+pub fn get_trash(conn: &Connection) -> Result<Vec<Bookmark>> {
+    let mut stmt = conn.prepare(&format!("{} WHERE b.deleted_at IS NOT NULL GROUP BY b.id ORDER BY b.deleted_at DESC",
+        SELECT_BOOKMARKS))?;
+    let bookmarks: Vec<_> = stmt.query_map([], to_bookmark)?.filter_map(rusqlite::Result::ok).collect();
+
+    Ok(bookmarks)
+}
+
+pub fn restore_entry(conn: &Connection, id: i32) -> Result<usize> {
+    let before = snapshot(conn, id)?;
+    let count = conn.execute("UPDATE bookmarks SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL", params![id])?;
+
+    if count > 0 {
+        record_change(conn, "restore", id, before.as_ref(), snapshot(conn, id)?.as_ref())?;
+    }
+
+    Ok(count)
+}
+
+pub fn empty_trash(conn: &Connection) -> Result<usize> {
+    delete_trash(conn, 0, "purge")
+}
+
+/// Deletes bookmarks that have been in the trash for longer than the given number of seconds.
+pub fn purge_trash(conn: &Connection, older_than: i64) -> Result<usize> {
+    delete_trash(conn, older_than, "expire")
+}
+
+// Emptying the trash can be undone, expiry can't: it would only purge the bookmarks again on the next run.
+fn delete_trash(conn: &Connection, older_than: i64, op: &str) -> Result<usize> {
+    let expired = "SELECT id FROM bookmarks
+                   WHERE deleted_at IS NOT NULL AND deleted_at <= CAST(strftime('%s', 'now') AS INTEGER) - ?1";
+
+    let mut stmt = conn.prepare(&format!("{} WHERE b.id IN ({}) GROUP BY b.id", SELECT_BOOKMARKS, expired))?;
+    let bookmarks: Vec<_> = stmt.query_map(params![older_than], to_bookmark)?.filter_map(rusqlite::Result::ok).collect();
+    for bookmark in &bookmarks {
+        record_change(conn, op, bookmark.id, Some(bookmark), None)?;
+    }
+
+    conn.execute(&format!("DELETE FROM tags WHERE bookmark_id IN ({})", expired), params![older_than])?;
+    conn.execute(&format!("DELETE FROM events WHERE bookmark_id IN ({})", expired), params![older_than])?;
+
+    Ok(conn.execute(&format!("DELETE FROM bookmarks WHERE id IN ({})", expired), params![older_than])?)
+}
+
+// All changes made by one run of bookman share a batch, which `undo` reverts as a whole.
+fn batch() -> i64 {
+    static BATCH: OnceLock<i64> = OnceLock::new();
+
+    *BATCH.get_or_init(|| SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_micros() as i64))
+}
+
+fn record_change(conn: &Connection, op: &str, id: i32, before: Option<&Bookmark>, after: Option<&Bookmark>)
+    -> Result<()> {
+    if let (Some(before), Some(after)) = (before, after) && before.changed_fields(after).is_empty()
+        && before.deleted_at == after.deleted_at {
+        return Ok(());
+    }
+
+    append_history(conn, op, id, before, after, None)
+}
+
+// The history is append-only, undoing a change adds an "undo" entry pointing at it.
+fn append_history(conn: &Connection, op: &str, id: i32, before: Option<&Bookmark>, after: Option<&Bookmark>,
+    undoes: Option<i64>) -> Result<()> {
+    conn.execute("INSERT INTO history (batch, op, bookmark_id, before, after, at, undoes)
+                  VALUES (?1, ?2, ?3, ?4, ?5, strftime('%s', 'now'), ?6)",
+        params![batch(), op, id, before.map(serde_json::to_string).transpose()?,
+            after.map(serde_json::to_string).transpose()?, undoes])?;
+
+    Ok(())
+}
+
+// A change counts as undone once an "undo" entry points at it.
+const SELECT_CHANGES: &str =
+    "SELECT h.id, h.batch, h.op, h.bookmark_id, h.before, h.after, h.at,
+            EXISTS (SELECT 1 FROM history u WHERE u.undoes = h.id), h.undoes
+     FROM history h";
+
+fn to_change(row: &Row) -> rusqlite::Result<Change> {
+    let snapshot = |text: Option<String>| text.and_then(|t| serde_json::from_str(&t).ok());
+
+    Ok(Change {
+        id: row.get(0)?,
+        batch: row.get(1)?,
+        op: row.get(2)?,
+        bookmark_id: row.get(3)?,
+        before: snapshot(row.get(4)?),
+        after: snapshot(row.get(5)?),
+        at: row.get(6)?,
+        undone: row.get(7)?,
+        undoes: row.get(8)?,
+    })
+}
+
+/// Returns the recorded changes, newest first, optionally only those of one bookmark.
+pub fn get_history(conn: &Connection, id: Option<i32>, limit: usize) -> Result<Vec<Change>> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE ?1 IS NULL OR h.bookmark_id = ?1 ORDER BY h.id DESC LIMIT ?2", SELECT_CHANGES))?;
+    let changes: Vec<_> = stmt.query_map(params![id, limit as i64], to_change)?.filter_map(rusqlite::Result::ok).collect();
+
+    Ok(changes)
+}
+
+/// Reverts the last `count` batches that were not undone yet and returns the number of reverted changes.
+pub fn undo(conn: &Connection, count: usize) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+
+    let pending = "h.op NOT IN ('expire', 'undo') AND NOT EXISTS (SELECT 1 FROM history u WHERE u.undoes = h.id)";
+    let mut stmt = tx.prepare(&format!(
+        "{} WHERE {} AND h.batch IN (SELECT DISTINCT h.batch FROM history h WHERE {} ORDER BY h.batch DESC LIMIT ?1)
+         ORDER BY h.id DESC", SELECT_CHANGES, pending, pending))?;
+    let changes: Vec<_> = stmt.query_map(params![count as i64], to_change)?.filter_map(rusqlite::Result::ok).collect();
+    drop(stmt);
+
+    for change in &changes {
+        let before = snapshot(&tx, change.bookmark_id)?;
+        revert(&tx, change)?;
+        let after = snapshot(&tx, change.bookmark_id)?;
+        append_history(&tx, "undo", change.bookmark_id, before.as_ref(), after.as_ref(), Some(change.id))?;
+    }
+
+    tx.commit()?;
+
+    Ok(changes.len())
+}
+
+// Writes the bookmark back as it was before the change, `undo` records the result.
+fn revert(conn: &Connection, change: &Change) -> Result<()> {
+    let id = change.bookmark_id;
+
+    match (change.op.as_str(), &change.before) {
+        ("insert", _) => {
+            conn.execute("DELETE FROM tags WHERE bookmark_id = ?1", params![id])?;
+            conn.execute("DELETE FROM events WHERE bookmark_id = ?1", params![id])?;
+            conn.execute("DELETE FROM bookmarks WHERE id = ?1", params![id])?;
+        }
+        ("update", Some(before)) => {
+            write_entry(conn, before)?;
+        }
+        ("delete" | "restore", Some(before)) => {
+            conn.execute("UPDATE bookmarks SET deleted_at = ?1 WHERE id = ?2", params![before.deleted_at, id])?;
+        }
+        ("purge", Some(before)) => {
+            conn.execute(
+                "INSERT OR IGNORE INTO bookmarks (id, name, url, description, folder, visits, last_visited, added,
+                     link_status, snippet, state, starred, rating, deleted_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                params![id, before.name, before.url, before.description, before.folder, before.visits,
+                    before.last_visited, before.added, before.link_status, before.snippet, before.state.as_str(),
+                    before.starred, before.rating, before.deleted_at])?;
+            write_tags(conn, id, &before.tags)?;
+        }
+        _ => {
+            return Err(BookmanError::InvalidInput(format!("change {} ({}) can't be undone", change.id, change.op)));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn insert_collection(conn: &Connection, name: &str, query: &str) -> Result<()> {
+    conn.execute("INSERT INTO collections (name, query) VALUES (?1, ?2)", params![name, query])?;
+
+    Ok(())
+}
+
+pub fn get_collection(conn: &Connection, name: &str) -> Result<Option<String>> {
+    let mut stmt = conn.prepare("SELECT query FROM collections WHERE name = ?1")?;
+
+    let mut query = stmt.query(params![name])?;
+    if let Some(row) = query.next()? {
+        return Ok(Some(row.get(0)?));
+    }
+
+    Ok(None)
+}
+
+pub fn get_collections(conn: &Connection) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare("SELECT name, query FROM collections ORDER BY name")?;
+    let collections: Vec<_> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.filter_map(rusqlite::Result::ok).collect();
+
+    Ok(collections)
+}
+
+pub fn remove_collection(conn: &Connection, name: &str) -> Result<usize> {
+    Ok(conn.execute("DELETE FROM collections WHERE name = ?1", params![name])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        new_table(&conn).unwrap();
+        register_functions(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn undo_reverts_updates_and_removal() {
+        let conn = memory_db();
+        // Added without a history entry, so only the changes below are reverted.
+        conn.execute("INSERT INTO bookmarks (name, url, description, folder) VALUES ('Rust', 'https://rust-lang.org', '', '')", []).unwrap();
+        let id = conn.last_insert_rowid() as i32;
+        write_tags(&conn, id, &["lang".to_string()]).unwrap();
+
+        update_entry(&conn, &Bookmark { name: "Rust lang".to_string(), ..get_entry(&conn, id).unwrap() }).unwrap();
+        set_tags(&conn, id, &[]).unwrap();
+        remove_entry(&conn, id).unwrap();
+        assert!(get_entry(&conn, id).is_err());
+
+        assert_eq!(undo(&conn, 1).unwrap(), 3);
+        let restored = get_entry(&conn, id).unwrap();
+        assert_eq!(restored.name, "Rust");
+        assert_eq!(restored.tags, vec!["lang".to_string()]);
+        assert_eq!(undo(&conn, 1).unwrap(), 0);
+
+        // The undo is appended, the changes it reverted are left as they were.
+        let history = get_history(&conn, None, 10).unwrap();
+        assert_eq!(history.len(), 6);
+        assert!(history[..3].iter().all(|change| change.op == "undo" && !change.undone));
+        assert!(history[3..].iter().all(|change| change.undone && change.op != "undo"));
+        assert_eq!(history[0].undoes, Some(history[5].id));
+        assert_eq!(history[0].after.as_ref().unwrap().name, "Rust");
+    }
+
+    #[test]
+    fn undo_removes_inserted_bookmarks() {
+        let conn = memory_db();
+        let id = insert_entry(&conn, &Bookmark { name: "Docs".to_string(), ..Default::default() }).unwrap();
+
+        undo(&conn, 1).unwrap();
+        assert!(snapshot(&conn, id).unwrap().is_none());
+    }
 }
-*/
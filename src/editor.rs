@@ -0,0 +1,274 @@
+/*
+    This file is a part of bookman software.
+
+    This module contains logic required to edit bookmarks in $EDITOR.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use crate::config;
+use crate::entry::{Bookmark, State};
+use crate::error::{BookmanError, Result};
+use crate::utils;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HEADER: &str = "\
+# Edit the bookmarks below, then save the file and close the editor.
+# Bookmarks removed from this file are left unchanged.
+";
+
+#[derive(Serialize, Deserialize)]
+struct Document {
+    #[serde(default)]
+    bookmark: Vec<Entry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    id: i32,
+    name: String,
+    url: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    folder: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    starred: bool,
+    #[serde(default)]
+    rating: u8,
+}
+
+// The file holds decrypted bookmarks, so it's kept in a new directory only we can
+// enter and removed together with it (and any editor backups) after the edit.
+struct TempFile {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl TempFile {
+    fn create(text: &str) -> Result<TempFile> {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.subsec_nanos());
+        let dir = env::temp_dir().join(format!("bookman-{}-{}", process::id(), nanos));
+
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        // Fails if anything, including a symlink, already exists at that path.
+        builder.create(&dir)?;
+
+        let file = TempFile { path: dir.join("bookmarks.toml"), dir };
+        write_private(&file.path, text)?;
+
+        Ok(file)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Opens the bookmarks in $EDITOR and returns the ones that were changed.
+pub fn edit_bookmarks(bookmarks: &[Bookmark], force: bool) -> Result<Vec<Bookmark>> {
+    let document = Document {
+        bookmark: bookmarks.iter().map(|bm| Entry {
+            id: bm.id,
+            name: bm.name.clone(),
+            url: bm.url.clone(),
+            description: bm.description.clone(),
+            tags: bm.tags.clone(),
+            folder: bm.folder.clone(),
+            state: bm.state.as_str().to_string(),
+            starred: bm.starred,
+            rating: bm.rating.unwrap_or(0),
+        }).collect(),
+    };
+
+    let file = TempFile::create(&format!("{}\n{}", HEADER, toml::to_string_pretty(&document)?))?;
+
+    loop {
+        open_editor(&file.path)?;
+
+        match collect_changes(bookmarks, &fs::read_to_string(&file.path)?, force) {
+            Ok(changed) => {
+                return Ok(changed);
+            }
+            Err(err) => {
+                eprintln!("\x1b[1;31mError:\x1b[0m {}", err);
+                if !utils::confirm("Re-open the editor?") {
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+fn write_private(path: &Path, text: &str) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path)?.write_all(text.as_bytes())?;
+
+    Ok(())
+}
+
+fn open_editor(path: &Path) -> Result<()> {
+    // An empty variable counts as unset.
+    let set = |var| env::var(var).ok().filter(|value: &String| !value.trim().is_empty());
+    let editor = set("VISUAL").or_else(|| set("EDITOR")).unwrap_or_else(|| "vi".to_string());
+
+    // E.g. after clicking Edit on a notification there is no terminal for the editor.
+    let editor = if io::stdin().is_terminal() || config::TERMINAL.is_empty() {
+        editor
+    } else {
+        format!("{} {}", config::TERMINAL, editor)
+    };
+
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().ok_or_else(|| BookmanError::External("$EDITOR is empty".to_string()))?;
+
+    let status = Command::new(program).args(parts).arg(path).status()?;
+    if !status.success() {
+        return Err(BookmanError::External(format!("{} exited with {}", program, status)));
+    }
+
+    Ok(())
+}
+
+fn collect_changes(original: &[Bookmark], text: &str, force: bool) -> Result<Vec<Bookmark>> {
+    let document: Document = toml::from_str(text)?;
+
+    let mut seen = HashSet::new();
+    let mut changed = Vec::new();
+
+    for entry in document.bookmark {
+        let old = original.iter()
+            .find(|bm| bm.id == entry.id)
+            .ok_or_else(|| BookmanError::InvalidInput(format!("bookmark {} was not part of this edit", entry.id)))?;
+
+        if !seen.insert(entry.id) {
+            return Err(BookmanError::InvalidInput(format!("bookmark {} appears more than once", entry.id)));
+        }
+        if entry.name.trim().is_empty() {
+            return Err(BookmanError::InvalidInput(format!("bookmark {} has an empty name", entry.id)));
+        }
+
+        let state = if entry.state.trim().is_empty() { old.state } else {
+            State::parse(entry.state.trim())
+                .ok_or_else(|| BookmanError::InvalidInput(
+                    format!("bookmark {}: unknown state '{}', expected unread, read or archived", entry.id, entry.state)))?
+        };
+
+        if entry.rating > 5 {
+            return Err(BookmanError::InvalidInput(format!("bookmark {}: rating must be between 0 (none) and 5", entry.id)));
+        }
+
+        let url = if entry.url.trim() == old.url { old.url.clone() } else {
+            utils::normalize_url(&entry.url, force)
+                .map_err(|err| BookmanError::InvalidInput(format!("bookmark {}: {}", entry.id, err)))?
+        };
+
+        let new = Bookmark {
+            id: entry.id,
+            name: entry.name.trim().to_string(),
+            url,
+            description: entry.description.trim().to_string(),
+            tags: utils::clean_tags(&entry.tags),
+            folder: entry.folder.trim().to_string(),
+            state,
+            starred: entry.starred,
+            rating: if entry.rating == 0 { None } else { Some(entry.rating) },
+            ..old.clone()
+        };
+
+        if !old.changed_fields(&new).is_empty() {
+            changed.push(new);
+        }
+    }
+
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn original() -> Vec<Bookmark> {
+        vec![
+            Bookmark { id: 1, name: "Rust".into(), url: "https://www.rust-lang.org/".into(), ..Default::default() },
+            Bookmark { id: 2, name: "Tokio".into(), url: "https://tokio.rs/".into(), rating: Some(4), ..Default::default() },
+        ]
+    }
+
+    fn entry(id: i32, url: &str, extra: &str) -> String {
+        format!("[[bookmark]]\nid = {}\nname = \"Name {}\"\nurl = \"{}\"\n{}\n", id, id, url, extra)
+    }
+
+    fn invalid(text: &str) -> bool {
+        matches!(collect_changes(&original(), text, false), Err(BookmanError::InvalidInput(_)))
+    }
+
+    #[test]
+    fn collects_changed_bookmarks_only() {
+        let unchanged = "[[bookmark]]\nid = 1\nname = \"Rust\"\nurl = \"https://www.rust-lang.org/\"\n";
+        let text = format!("{}{}", unchanged, entry(2, "docs.rs", "state = \"unread\"\nrating = 0"));
+
+        let changed = collect_changes(&original(), &text, false).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].url, "https://docs.rs/");
+        assert_eq!(changed[0].state, State::Unread);
+        assert_eq!(changed[0].rating, None);
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        assert!(invalid(&entry(3, "https://a.b/", "")));
+        assert!(invalid(&format!("{}{}", entry(1, "https://a.b/", ""), entry(1, "https://c.d/", ""))));
+        assert!(invalid(&entry(1, "https://a.b/", "state = \"done\"")));
+        assert!(invalid(&entry(1, "https://a.b/", "rating = 6")));
+        assert!(invalid(&entry(1, "notes", "")));
+    }
+
+    #[test]
+    fn force_keeps_the_url_as_written() {
+        let changed = collect_changes(&original(), &entry(1, "notes", ""), true).unwrap();
+        assert_eq!(changed[0].url, "notes");
+    }
+}
@@ -21,10 +21,151 @@
     USA
 */
 
-#[derive(Debug)]
+use crate::config;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use skim::{ItemPreview, PreviewContext, SkimItem};
+use std::borrow::Cow;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum State {
+    Unread,
+    #[default]
+    Read,
+    Archived,
+}
+
+impl State {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            State::Unread => "unread",
+            State::Read => "read",
+            State::Archived => "archived",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<State> {
+        match text {
+            "unread" => Some(State::Unread),
+            "read" => Some(State::Read),
+            "archived" => Some(State::Archived),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Bookmark {
     pub id: i32,
     pub name: String,
     pub url: String,
     pub description: String,
+    pub tags: Vec<String>,
+    pub folder: String,
+    pub visits: i64,
+    pub last_visited: Option<i64>,
+    pub added: Option<i64>,
+    pub link_status: Option<i64>,
+    pub snippet: String,
+    pub frecency: f64,
+    pub state: State,
+    pub starred: bool,
+    pub rating: Option<u8>,
+    pub deleted_at: Option<i64>,
+}
+
+/// A single recorded change of a bookmark, `before` and `after` are snapshots around it.
+/// Undoing a change records an "undo" change that `undoes` it.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub id: i64,
+    pub batch: i64,
+    pub op: String,
+    pub bookmark_id: i32,
+    pub before: Option<Bookmark>,
+    pub after: Option<Bookmark>,
+    pub at: i64,
+    pub undone: bool,
+    pub undoes: Option<i64>,
+}
+
+impl Bookmark {
+    /// Renders the bookmark as a single line using a template such as `config::DISPLAY_TEMPLATE`.
+    pub fn format(&self, template: &str) -> String {
+        template
+            .replace("{id}", &self.id.to_string())
+            .replace("{name}", &self.name)
+            .replace("{url}", &self.url)
+            .replace("{description}", &self.description)
+            .replace("{tags}", &self.tags.join(","))
+            .replace("{folder}", &self.folder)
+            .replace("{visits}", &self.visits.to_string())
+            .replace("{state}", self.state.as_str())
+    }
+
+    /// Names of the user-editable fields that differ between the two bookmarks.
+    pub fn changed_fields(&self, other: &Bookmark) -> Vec<&'static str> {
+        let mut fields = Vec::new();
+        if self.name != other.name { fields.push("name"); }
+        if self.url != other.url { fields.push("url"); }
+        if self.description != other.description { fields.push("description"); }
+        if self.tags != other.tags { fields.push("tags"); }
+        if self.folder != other.folder { fields.push("folder"); }
+        if self.state != other.state { fields.push("state"); }
+        if self.starred != other.starred { fields.push("starred"); }
+        if self.rating != other.rating { fields.push("rating"); }
+        fields
+    }
+
+    /// Renders all known details of the bookmark, one field per line.
+    pub fn details(&self) -> String {
+        let status = match self.link_status {
+            None => "unchecked".to_string(),
+            Some(0) => "unreachable".to_string(),
+            Some(code) => code.to_string(),
+        };
+
+        let mut details = format!(
+            "Name:         {}\nURL:          {}\nDescription:  {}\nTags:         {}\nFolder:       {}\n\
+             State:        {}\nStarred:      {}\nRating:       {}\nAdded:        {}\nLast visited: {}\nVisits:       {}\nLink status:  {}\n",
+            self.name,
+            self.url,
+            self.description,
+            self.tags.join(", "),
+            self.folder,
+            self.state.as_str(),
+            if self.starred { "yes" } else { "no" },
+            self.rating.map_or("none".to_string(), |r| "*".repeat(r as usize)),
+            format_time(self.added, "unknown"),
+            format_time(self.last_visited, "never"),
+            self.visits,
+            status,
+        );
+
+        if !self.snippet.is_empty() {
+            details.push_str(&format!("\n{}\n", self.snippet));
+        }
+
+        details
+    }
+}
+
+pub fn format_time(timestamp: Option<i64>, fallback: &str) -> String {
+    timestamp
+        .and_then(|ts| DateTime::from_timestamp(ts, 0))
+        .map(|dt| dt.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+impl SkimItem for Bookmark {
+    fn text(&self) -> Cow<'_, str> {
+        Cow::Owned(self.format(config::DISPLAY_TEMPLATE))
+    }
+
+    fn preview(&self, _context: PreviewContext) -> ItemPreview {
+        ItemPreview::Text(self.details())
+    }
 }
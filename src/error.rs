@@ -0,0 +1,142 @@
+/*
+    This file is a part of bookman software.
+
+    It defines the errors returned by the bookman library and the exit
+    codes the command line uses for them.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+pub type Result<T> = std::result::Result<T, BookmanError>;
+
+#[derive(Debug)]
+pub enum BookmanError {
+    /// A bookmark, collection or anything else asked for doesn't exist.
+    NotFound(String),
+    /// The database can't be decrypted with the configured passphrase.
+    WrongKey,
+    /// Something with the same name already exists.
+    Duplicate(String),
+    /// A page couldn't be fetched.
+    Network(String),
+    /// A query, an import file or an edited document is malformed.
+    Parse(String),
+    /// The clipboard can't be read or written.
+    Clipboard(String),
+    /// A value given by the user was rejected, e.g. an invalid URL.
+    InvalidInput(String),
+    /// The browser or the editor failed.
+    External(String),
+    Database(rusqlite::Error),
+    Io(io::Error),
+}
+
+impl BookmanError {
+    /// Exit code of the command line, 2 is also used by clap for usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            BookmanError::InvalidInput(_) => 2,
+            BookmanError::Parse(_) => 3,
+            BookmanError::NotFound(_) => 4,
+            BookmanError::Duplicate(_) => 5,
+            BookmanError::WrongKey => 6,
+            BookmanError::Network(_) => 7,
+            BookmanError::Clipboard(_) => 8,
+            BookmanError::External(_) => 9,
+            BookmanError::Database(_) => 10,
+            BookmanError::Io(_) => 11,
+        }
+    }
+}
+
+impl fmt::Display for BookmanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BookmanError::NotFound(message)
+            | BookmanError::Duplicate(message)
+            | BookmanError::InvalidInput(message)
+            | BookmanError::External(message) => write!(f, "{}", message),
+            BookmanError::WrongKey => write!(f, "wrong passphrase or not a bookman database"),
+            BookmanError::Network(message) => write!(f, "network error: {}", message),
+            BookmanError::Parse(message) => write!(f, "parse error: {}", message),
+            BookmanError::Clipboard(message) => write!(f, "clipboard error: {}", message),
+            BookmanError::Database(err) => write!(f, "database error: {}", err),
+            BookmanError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for BookmanError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BookmanError::Database(err) => Some(err),
+            BookmanError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for BookmanError {
+    fn from(err: rusqlite::Error) -> Self {
+        // SQLCipher can't tell a wrong key from a file that isn't a database at all.
+        match err.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::NotADatabase) => BookmanError::WrongKey,
+            _ => BookmanError::Database(err),
+        }
+    }
+}
+
+impl From<io::Error> for BookmanError {
+    fn from(err: io::Error) -> Self {
+        BookmanError::Io(err)
+    }
+}
+
+impl From<reqwest::Error> for BookmanError {
+    fn from(err: reqwest::Error) -> Self {
+        BookmanError::Network(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for BookmanError {
+    fn from(err: serde_json::Error) -> Self {
+        BookmanError::Parse(err.to_string())
+    }
+}
+
+impl From<toml::de::Error> for BookmanError {
+    fn from(err: toml::de::Error) -> Self {
+        BookmanError::Parse(err.to_string())
+    }
+}
+
+impl From<toml::ser::Error> for BookmanError {
+    fn from(err: toml::ser::Error) -> Self {
+        BookmanError::Parse(err.to_string())
+    }
+}
+
+impl From<arboard::Error> for BookmanError {
+    fn from(err: arboard::Error) -> Self {
+        BookmanError::Clipboard(err.to_string())
+    }
+}
@@ -0,0 +1,137 @@
+/*
+    This file is a part of bookman software.
+
+    This module contains writers used for exporting bookmarks to files.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use crate::entry::Bookmark;
+use crate::parser::LinkFormat;
+
+// Keeps bookmarks without a folder first, then the folders in alphabetical order.
+fn by_folder(bookmarks: &[Bookmark]) -> Vec<(&str, Vec<&Bookmark>)> {
+    let mut folders: Vec<&str> = bookmarks.iter().map(|bm| bm.folder.as_str()).collect();
+    folders.sort();
+    folders.dedup();
+
+    folders.into_iter()
+        .map(|folder| (folder, bookmarks.iter().filter(|bm| bm.folder == folder).collect()))
+        .collect()
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes bookmarks in the Netscape bookmark file format understood by all major browsers.
+pub fn to_html(bookmarks: &[Bookmark], title: &str) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <!-- This is an automatically generated file. -->\n\
+         <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n");
+    html.push_str(&format!("<TITLE>{}</TITLE>\n<H1>{}</H1>\n<DL><p>\n", escape_html(title), escape_html(title)));
+
+    for (folder, entries) in by_folder(bookmarks) {
+        let indent = if folder.is_empty() { "    " } else { "        " };
+        if !folder.is_empty() {
+            html.push_str(&format!("    <DT><H3>{}</H3>\n    <DL><p>\n", escape_html(folder)));
+        }
+
+        for bm in entries {
+            html.push_str(&format!("{}<DT><A HREF=\"{}\"", indent, escape_html(&bm.url)));
+            if let Some(added) = bm.added {
+                html.push_str(&format!(" ADD_DATE=\"{}\"", added));
+            }
+            if !bm.tags.is_empty() {
+                html.push_str(&format!(" TAGS=\"{}\"", escape_html(&bm.tags.join(","))));
+            }
+            html.push_str(&format!(">{}</A>\n", escape_html(&bm.name)));
+            if !bm.description.is_empty() {
+                html.push_str(&format!("{}<DD>{}\n", indent, escape_html(&bm.description)));
+            }
+        }
+
+        if !folder.is_empty() {
+            html.push_str("    </DL><p>\n");
+        }
+    }
+
+    html.push_str("</DL><p>\n");
+    html
+}
+
+pub fn to_markdown(bookmarks: &[Bookmark], title: &str) -> String {
+    let mut markdown = format!("# {}\n", title);
+
+    for (folder, entries) in by_folder(bookmarks) {
+        markdown.push('\n');
+        if !folder.is_empty() {
+            markdown.push_str(&format!("## {}\n\n", folder));
+        }
+
+        for bm in entries {
+            markdown.push_str(&format!("- [{}]({})", bm.name.replace(']', "\\]"), bm.url.replace(')', "%29")));
+            if !bm.description.is_empty() {
+                markdown.push_str(&format!(" - {}", bm.description));
+            }
+            for tag in &bm.tags {
+                markdown.push_str(&format!(" `{}`", tag));
+            }
+            markdown.push('\n');
+        }
+    }
+
+    markdown
+}
+
+pub fn to_json(bookmarks: &[Bookmark]) -> String {
+    serde_json::to_string_pretty(bookmarks).unwrap_or_default() + "\n"
+}
+
+/// A single link to the bookmark, e.g. `[name](url)` for Markdown.
+pub fn to_link(bm: &Bookmark, format: LinkFormat) -> String {
+    match format {
+        LinkFormat::Plain => bm.url.clone(),
+        LinkFormat::Markdown => format!("[{}]({})", bm.name.replace(']', "\\]"), bm.url.replace(')', "%29")),
+        LinkFormat::Html => format!("<a href=\"{}\">{}</a>", escape_html(&bm.url), escape_html(&bm.name)),
+        LinkFormat::Org => format!("[[{}][{}]]", bm.url.replace(']', "%5D"), bm.name.replace(']', "\\]")),
+        LinkFormat::Rst => format!("`{} <{}>`_", bm.name.replace('`', "\\`").replace('<', "\\<"), bm.url),
+        LinkFormat::Title => bm.name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_links() {
+        let bm = Bookmark { name: "Rust <book>".to_string(), url: "https://doc.rust-lang.org/book/".to_string(), ..Default::default() };
+
+        assert_eq!(to_link(&bm, LinkFormat::Plain), "https://doc.rust-lang.org/book/");
+        assert_eq!(to_link(&bm, LinkFormat::Markdown), "[Rust <book>](https://doc.rust-lang.org/book/)");
+        assert_eq!(to_link(&bm, LinkFormat::Html), "<a href=\"https://doc.rust-lang.org/book/\">Rust &lt;book&gt;</a>");
+        assert_eq!(to_link(&bm, LinkFormat::Org), "[[https://doc.rust-lang.org/book/][Rust <book>]]");
+        assert_eq!(to_link(&bm, LinkFormat::Rst), "`Rust \\<book> <https://doc.rust-lang.org/book/>`_");
+        assert_eq!(to_link(&bm, LinkFormat::Title), "Rust <book>");
+    }
+}
@@ -0,0 +1,46 @@
+/*
+    This file is a part of bookman software.
+
+    It is the root of the bookman library. The Store gives access to the
+    bookmark database, manager implements the commands of the CLI.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+pub mod entry;
+pub mod error;
+pub mod store;
+pub mod memory_store;
+pub mod query;
+pub mod parser;
+pub mod manager;
+pub mod config;
+pub mod export;
+pub mod picker;
+pub mod utils;
+pub mod clipboard;
+pub mod tui;
+mod db_driver;
+mod notify;
+mod editor;
+mod browser;
+
+pub use entry::{Bookmark, State};
+pub use error::{BookmanError, Result};
+pub use store::{BookmarkStore, Store};
+pub use memory_store::MemoryStore;
@@ -19,64 +19,112 @@
     USA
 */
 
-pub mod entry;
-pub mod utils;
-pub mod parser;
-pub mod db_driver;
-pub mod manager;
-pub mod config;
-pub mod notify;
-
-use std::fs;
 use std::env;
 use std::path;
-use crate::parser::Commands;
-use clap::Parser;
-use rusqlite::Connection;
+use std::process;
+use bookman::{config, manager, parser, BookmanError, State, Store};
+use bookman::parser::{CollectionCommands, Commands, Picker, TrashCommands};
+use clap::{Parser, ValueEnum};
 
 fn main() {
-    let home: String  = env::var("HOME").expect("env variable '$HOME' is not set.");
-    let db_file = path::PathBuf::from(home).join(config::DB_FILE);
+    let cli = parser::Cli::parse();
 
-    // Make sure the db path exists
-    let db_dir = path::Path::new(&db_file).parent().unwrap();
-    if !db_dir.exists() {
-        fs::create_dir_all(db_dir).expect("Failed to create database directory");
+    if let Err(err) = run(cli.command, cli.picker) {
+        eprintln!("\x1b[1;31mError:\x1b[0m {}", err);
+        process::exit(err.exit_code());
     }
+}
 
-    // Connect to the database
-    let conn = Connection::open(db_file).expect("Failed to open database");
-
-    // Establish database encryption
-    conn.pragma_update(None, "key", config::DB_PASS).unwrap();
+fn run(command: Commands, picker: Option<Picker>) -> bookman::Result<()> {
+    // The background clipboard helper never touches the bookmarks, so it doesn't open the database.
+    if let Commands::HoldClipboard { html, keep } = command {
+        return manager::hold_clipboard(html, keep);
+    }
 
-    manager::new(&conn);
+    let picker = match picker {
+        Some(picker) => picker,
+        None => Picker::from_str(config::PICKER, true)
+            .map_err(|_| BookmanError::InvalidInput(format!("unknown picker '{}'", config::PICKER)))?,
+    };
+    let home = env::var("HOME").map_err(|_| BookmanError::InvalidInput("env variable '$HOME' is not set".to_string()))?;
+    let store = Store::open(&path::PathBuf::from(home).join(config::DB_FILE), config::DB_PASS)?;
 
-    let cli = parser::Cli::parse();
-    match cli.command {
-        Commands::Add { clipboard } => {
-            manager::add(&conn, clipboard);
+    match command {
+        Commands::Add(args) => {
+            manager::add(&store, &args, State::Read)
+        },
+        Commands::Later(args) => {
+            manager::add(&store, &args, State::Unread)
+        },
+        Commands::Queue { notify } => {
+            manager::queue(&store, notify)
+        },
+        Commands::Search { sort, collection, query } => {
+            let query = manager::scope(&store, collection.as_deref(), &parser::join_query(&query))?;
+            manager::open(&store, &query, sort, picker)
+        },
+        Commands::Open { sort, collection, query } => {
+            let query = manager::scope(&store, collection.as_deref(), &parser::join_query(&query))?;
+            manager::open(&store, &query, sort, picker)
         },
-        Commands::Search => {
-            if let Some(url) = manager::search(&conn) {
-                #[cfg(debug_assertions)]
-                {
-                    notify::send_notification("Bookman", &format!("Selected: {}", url));
-                    println!("Selected URL: {}", url);
-                }
-            }
+        Commands::List { sort, collection, query } => {
+            let query = manager::scope(&store, collection.as_deref(), &parser::join_query(&query))?;
+            manager::list(&store, &query, sort)
         },
-        Commands::Edit { id } => {
-            manager::edit(&conn, id);
+        Commands::Find { sort, query } => {
+            manager::find(&store, &parser::join_query(&query), sort)
         },
-        Commands::Remove { id } => {
-            manager::remove(&conn, id);
+        Commands::Edit(args) => {
+            manager::edit(&store, &args)
         },
-        Commands::Clip => {
-            manager::clip(&conn);
+        Commands::Remove { yes, target } => {
+            manager::remove(&store, &parser::join_query(&target), yes, picker)
+        },
+        Commands::Clip { sort, no_wait, format } => {
+            manager::clip(&store, sort, format, !no_wait, picker)
+        },
+        Commands::Tui => {
+            manager::tui(&store)
+        },
+        Commands::HoldClipboard { .. } => {
+            unreachable!("handled before the store is opened")
         },
         Commands::Import { path } => {
-            manager::import(&conn, &path);
-        }
+            manager::import(&store, &path)
+        },
+        Commands::Export { format, output, query } => {
+            manager::export(&store, &parser::join_query(&query), format, output.as_deref())
+        },
+        Commands::Collection { command } => match command {
+            CollectionCommands::Add { name, query } => {
+                manager::collection_add(&store, &name, &parser::join_query(&query))
+            },
+            CollectionCommands::List => {
+                manager::collection_list(&store)
+            },
+            CollectionCommands::Remove { name } => {
+                manager::collection_remove(&store, &name)
+            },
+            CollectionCommands::Export { name, format, output } => {
+                manager::collection_export(&store, &name, format, output.as_deref())
+            },
+        },
+        Commands::Trash { command } => match command {
+            TrashCommands::List => {
+                manager::trash_list(&store)
+            },
+            TrashCommands::Restore { id } => {
+                manager::trash_restore(&store, id)
+            },
+            TrashCommands::Empty => {
+                manager::trash_empty(&store)
+            },
+        },
+        Commands::History { id, limit } => {
+            manager::history(&store, id, limit)
+        },
+        Commands::Undo { count } => {
+            manager::undo(&store, count)
+        },
     }
 }
@@ -21,183 +21,733 @@
     USA
 */
 
-use crate::entry::Bookmark;
-use crate::db_driver;
+use crate::entry::{self, Bookmark, State};
+use crate::error::{BookmanError, Result};
+use crate::store::BookmarkStore;
 use crate::utils;
+use crate::clipboard;
+use crate::tui;
 use crate::parser;
-use crate::notify;
+use crate::notify::{self, Event};
+use crate::editor;
+use crate::browser;
+use crate::picker;
+use crate::picker::Action;
+use crate::query::Query;
+use crate::config;
+use crate::export;
+use crate::parser::{AddArgs, EditArgs, Format, LinkFormat, Picker, Sort};
 
-use rusqlite::Connection;
-use skim::prelude::SkimOptionsBuilder;
-use skim::{Skim, SkimItemReceiver, SkimItemSender};
-use std::sync::Arc;
+use std::fs;
+use std::io::Read;
 
-pub fn new(conn: &Connection) {
-    match db_driver::new_table(conn) {
-        Ok(()) => {
-            return;
-        }
-        Err(err) => {
-            utils::sql_driver_error(err);
-        }
-    }
-}
+use clap::ValueEnum;
 
-pub fn add(conn: &Connection, from_clipboard: bool) {
-    if from_clipboard {
-        let name: String;
-        let description = String::from("Fetched");
+pub(crate) fn filtered(store: &dyn BookmarkStore, query: &str, order: Sort) -> Result<Vec<Bookmark>> {
+    let mut bookmarks = store.filter(&Query::parse(query)?)?;
+    sort(&mut bookmarks, order);
 
-        let url = utils::copy_from_clipboard();
-        match utils::fetch_page(&url) {
-            Ok(text) => {
-                match parser::parse_html_text(&text) {
-                    Ok(title) => {
-                        name = title.to_string();
+    Ok(bookmarks)
+}
+
+pub fn add(store: &dyn BookmarkStore, args: &AddArgs, state: State) -> Result<()> {
+    let id = match args.url.as_deref() {
+        Some("-") => {
+            let mut saved = 0;
+            for line in utils::read_stdin_lines() {
+                match add_url(store, &line, args, state) {
+                    Ok(_) => {
+                        saved += 1;
                     }
-                    Err(_) => {
-                        name = "None".to_string();
+                    Err(err @ BookmanError::InvalidInput(_)) => {
+                        eprintln!("\x1b[1;33mWarning:\x1b[0m skipping: {}", err);
+                    }
+                    Err(err) => {
+                        return Err(err);
                     }
                 }
             }
-            Err(_) => {
-                name = "None".to_string();
+
+            notify::send_notification(Event::Saved, &format!("{} bookmarks are saved!", saved));
+            #[cfg(debug_assertions)]
+            {
+                println!("{} bookmarks added!", saved);
             }
+            return Ok(());
         }
+        Some(url) => add_url(store, url, args, state)?,
+        None if args.clipboard => add_url(store, &clipboard::paste()?, args, state)?,
+        None => {
+            let (name, url, description) = utils::prompt_user()?;
+            let url = utils::normalize_url(&url, args.force)?;
+            store.add(&Bookmark {
+                name,
+                url,
+                description,
+                tags: args.tag.clone(),
+                folder: args.folder.clone().unwrap_or_default(),
+                state,
+                starred: args.star,
+                rating: args.rating,
+                ..Default::default()
+            })?
+        }
+    };
 
-        match db_driver::insert_entry(conn, &name, &url, &description) {
-            Ok(()) => {
-                notify::send_notification("Bookman", "Bookmark is saved!");
-                #[cfg(debug_assertions)]
-                {
-                    println!("Bookmark added!");
-                }
-            }
-            Err(err) => {
-                utils::sql_driver_error(err);
-            }
+    #[cfg(debug_assertions)]
+    {
+        println!("Bookmark added!");
+    }
+
+    notify_saved(store, id, args.url.is_none() && args.clipboard)
+}
+
+// Offers to open, edit or undo the bookmark that was just saved. The buttons keep bookman
+// waiting, so they're only shown for `add --clipboard`, which is usually run from a hotkey.
+fn notify_saved(store: &dyn BookmarkStore, id: i32, actions: bool) -> Result<()> {
+    if !actions || !config::NOTIFY_ACTIONS {
+        notify::send_notification(Event::Saved, "Bookmark is saved!");
+        return Ok(());
+    }
+
+    let actions = [("open", "Open"), ("edit", "Edit"), ("undo", "Undo")];
+    match notify::ask(Event::Saved, "Bookmark is saved!", &actions).as_deref() {
+        Some("open") => open_bookmark(store, &store.get(id)?),
+        Some("edit") => edit_in_editor(store, &[store.get(id)?], false),
+        Some("undo") => {
+            // Only this bookmark, a command run meanwhile may have recorded newer changes.
+            store.remove(id)?;
+            notify::send_notification(Event::Removed, "Bookmark is removed!");
+            Ok(())
         }
-    } else {
-        if let Some((name, url, description)) = utils::prompt_user() {
-            match db_driver::insert_entry(conn, &name, &url, &description) {
-                Ok(()) => {
-                    notify::send_notification("Bookman", "Bookmark is saved!");
-                    #[cfg(debug_assertions)]
-                    {
-                        println!("Bookmark added!");
-                    }
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn add_url(store: &dyn BookmarkStore, url: &str, args: &AddArgs, state: State) -> Result<i32> {
+    let url = utils::normalize_url(url, args.force)?;
+
+    store.add(&Bookmark { state, ..complete_entry(&url, args) })
+}
+
+// Builds a bookmark from the command line, fetching whatever is missing from the page itself.
+fn complete_entry(url: &str, args: &AddArgs) -> Bookmark {
+    let mut bookmark = Bookmark {
+        url: url.trim().to_string(),
+        name: args.name.clone().unwrap_or_default(),
+        description: args.description.clone().unwrap_or_default(),
+        tags: args.tag.clone(),
+        folder: args.folder.clone().unwrap_or_default(),
+        starred: args.star,
+        rating: args.rating,
+        ..Default::default()
+    };
+
+    if !args.no_fetch {
+        match utils::fetch_page(&bookmark.url) {
+            Ok((status, text)) => {
+                bookmark.link_status = Some(status as i64);
+                bookmark.snippet = parser::parse_html_snippet(&text, 300).unwrap_or_default();
+
+                if args.name.is_none() && let Ok(title) = parser::parse_html_text(&text) {
+                    bookmark.name = title;
                 }
-                Err(err) => {
-                   utils::sql_driver_error(err);
+                if args.description.is_none() && let Ok(description) = parser::parse_html_description(&text) {
+                    bookmark.description = description;
                 }
             }
-        } else {
-            utils::user_input_error()
+            Err(_) => {
+                bookmark.link_status = Some(0);
+            }
         }
     }
+
+    if bookmark.name.trim().is_empty() {
+        bookmark.name = bookmark.url.clone();
+    }
+
+    bookmark
 }
 
-pub fn search(conn: &Connection) -> Option<String> {
-    match db_driver::get_iterator(conn) {
-        Ok(bookmarks) => {
-            let bookmarks: Vec<Bookmark> = bookmarks;
+/// Combines the query of a saved collection with an additional query.
+pub fn scope(store: &dyn BookmarkStore, collection: Option<&str>, query: &str) -> Result<String> {
+    match collection {
+        Some(name) => Ok(format!("{} {}", store.collection(name)?, query)),
+        None => Ok(query.to_string()),
+    }
+}
 
-            let options = SkimOptionsBuilder::default().build().unwrap();
-            let (tx, rx): (SkimItemSender, SkimItemReceiver) = skim::prelude::unbounded();
-            for bm in &bookmarks {
-                let line = format!("{} | {} | {}", bm.id, bm.name, bm.url);
-                let _ = tx.send(Arc::new(line));
-            }
-            drop(tx);
+pub fn search(store: &dyn BookmarkStore, query: &str, order: Sort, default: Action, menu: Picker) -> Result<()> {
+    let bookmarks = filtered(store, query, order)?;
 
-            let selected = Skim::run_with(&options, Some(rx))?.selected_items;
-            if let Some(selected_item) = selected.first() {
-                let selected_text = selected_item.output();
-                if let Some(bookmark) = bookmarks.iter().find(|b| selected_text.contains(&b.url)) {
-                    return Some(bookmark.url.clone());
-                }
+    if let Some((action, selected)) = picker::pick(bookmarks, default, menu)? {
+        run_action(store, action, &selected)?;
+    }
+
+    Ok(())
+}
+
+pub fn open(store: &dyn BookmarkStore, query: &str, order: Sort, menu: Picker) -> Result<()> {
+    search(store, query, order, Action::Open, menu)
+}
+
+pub fn list(store: &dyn BookmarkStore, query: &str, order: Sort) -> Result<()> {
+    for bookmark in filtered(store, query, order)? {
+        println!("{}", bookmark.format(config::DISPLAY_TEMPLATE));
+    }
+
+    Ok(())
+}
+
+pub fn queue(store: &dyn BookmarkStore, digest: bool) -> Result<()> {
+    let mut unread = filtered(store, "is:unread", Sort::Id)?;
+    unread.sort_by_key(|bm| (bm.added, bm.id));
+
+    if digest {
+        if !unread.is_empty() {
+            notify::send_notification(Event::Queue, &format!("{} unread bookmarks in the queue", unread.len()));
+        }
+        return Ok(());
+    }
+
+    for bookmark in &unread {
+        println!("{}", bookmark.format(config::DISPLAY_TEMPLATE));
+    }
+
+    Ok(())
+}
+
+pub fn find(store: &dyn BookmarkStore, query: &str, order: Sort) -> Result<()> {
+    for bookmark in filtered(store, query, order)? {
+        println!("{}", bookmark.url);
+    }
+
+    Ok(())
+}
+
+fn sort(bookmarks: &mut [Bookmark], order: Sort) {
+    match order {
+        Sort::Id => bookmarks.sort_by_key(|bm| bm.id),
+        Sort::Name => bookmarks.sort_by_key(|bm| bm.name.to_lowercase()),
+        Sort::Added => bookmarks.sort_by_key(|bm| std::cmp::Reverse((bm.added, bm.id))),
+        Sort::Rating => bookmarks.sort_by_key(|bm| std::cmp::Reverse((bm.rating, bm.starred))),
+        Sort::Frecency => bookmarks.sort_by(|a, b| {
+            b.frecency.total_cmp(&a.frecency)
+                .then(b.last_visited.cmp(&a.last_visited))
+                .then(a.id.cmp(&b.id))
+        }),
+    }
+}
+
+fn run_action(store: &dyn BookmarkStore, action: Action, selected: &[Bookmark]) -> Result<()> {
+    match action {
+        Action::Open => {
+            for bookmark in selected {
+                open_bookmark(store, bookmark)?;
             }
-            None
         }
-        Err(err) => {
-            utils::sql_driver_error(err);
+        Action::CopyUrl | Action::CopyMarkdown => {
+            let format = if action == Action::CopyMarkdown { LinkFormat::Markdown } else { LinkFormat::Plain };
+            copy(store, format, selected, true)?;
+        }
+        Action::Edit => {
+            edit_in_editor(store, selected, false)?;
+        }
+        Action::Delete => {
+            remove_bookmarks(store, selected, false)?;
+        }
+        Action::Tag => {
+            let mut answer = String::new();
+            println!("Enter tags to add (prefix with '-' to remove):");
+            std::io::stdin().read_line(&mut answer)?;
+
+            let (added, removed) = tag_changes(&answer);
+            retag(store, selected, &added, &removed)?;
+        }
+        Action::Archive => {
+            for bookmark in selected {
+                store.set_state(bookmark.id, State::Archived)?;
+            }
+            notify::send_notification(Event::Updated, &format!("{} bookmarks are archived!", selected.len()));
         }
     }
+
+    Ok(())
 }
 
-pub fn edit(conn: &Connection, id: i32) {
-    match db_driver::get_entry(conn, id) {
-        Ok(bookmark) => {
-            let old: Bookmark = bookmark;
+pub(crate) fn open_bookmark(store: &dyn BookmarkStore, bookmark: &Bookmark) -> Result<()> {
+    browser::open(bookmark)?;
+    store.record_visit(bookmark.id)?;
+    if bookmark.state == State::Unread {
+        store.set_state(bookmark.id, State::Read)?;
+    }
 
-            if let Some((new_name, new_url, new_description)) = utils::prompt_user() {
+    #[cfg(debug_assertions)]
+    {
+        println!("Opened URL: {}", bookmark.url);
+    }
 
-                let name = if new_name.trim().is_empty() { old.name.to_string() } else { new_name.trim().to_string() };
-                let url = if new_url.trim().is_empty() { old.url.to_string() } else { new_url.trim().to_string() };
-                let description = if new_description.trim().is_empty() { old.description.to_string() } else { new_description.to_string() };
+    Ok(())
+}
 
-                match db_driver::update_entry(conn, id, &name, &url, &description) {
-                    Ok(()) => {
-                        notify::send_notification("Bookman", "Bookmark is updated!");
-                        #[cfg(debug_assertions)]
-                        {
-                            println!("Bookmark updated!");
-                        }
-                        return;
-                    }
-                    Err(err) => {
-                        utils::sql_driver_error(err);
-                    }
-                }
-            } else {
-                utils::user_input_error();
+pub(crate) fn copy(store: &dyn BookmarkStore, format: LinkFormat, selected: &[Bookmark], wait: bool) -> Result<()> {
+    let links = selected.iter().map(|bm| export::to_link(bm, format)).collect::<Vec<_>>().join("\n");
+    // HTML goes along with the plain URLs, for the apps that only paste text.
+    let (text, html) = if format == LinkFormat::Html {
+        (selected.iter().map(|bm| bm.url.as_str()).collect::<Vec<_>>().join("\n"), Some(links.as_str()))
+    } else {
+        (links.clone(), None)
+    };
+
+    for bookmark in selected {
+        store.record_event(bookmark.id, "copy")?;
+    }
+
+    notify::send_notification(Event::Copied, &format!("Copied to clipboard!: {}", links));
+    if wait {
+        clipboard::copy(&text, html)
+    } else {
+        clipboard::copy_in_background(&text, html)
+    }
+}
+
+/// Splits "rust, -old" into the tags to add and the ones to remove.
+pub(crate) fn tag_changes(answer: &str) -> (Vec<String>, Vec<String>) {
+    let (removed, added): (Vec<String>, Vec<String>) = answer
+        .split([',', ' '])
+        .filter(|t| !t.trim().is_empty())
+        .map(|t| t.trim().to_string())
+        .partition(|t| t.starts_with('-'));
+    let removed: Vec<String> = removed.iter().map(|t| t.trim_start_matches('-').to_string()).collect();
+
+    (added, removed)
+}
+
+pub(crate) fn retag(store: &dyn BookmarkStore, selected: &[Bookmark], added: &[String], removed: &[String]) -> Result<()> {
+    let removed = utils::clean_tags(removed);
+
+    for bookmark in selected {
+        let mut tags = bookmark.tags.clone();
+        tags.extend_from_slice(added);
+        tags.retain(|t| !removed.contains(t));
+
+        store.set_tags(bookmark.id, &tags)?;
+    }
+
+    notify::send_notification(Event::Updated, &format!("{} bookmarks are tagged!", selected.len()));
+
+    Ok(())
+}
+
+pub fn edit(store: &dyn BookmarkStore, args: &EditArgs) -> Result<()> {
+    let selected = edit_targets(store, args)?;
+
+    if args.star || args.unstar || args.rating.is_some() {
+        rate(store, &selected, args)?;
+        notify::send_notification(Event::Updated, &format!("{} bookmarks are updated!", selected.len()));
+        Ok(())
+    } else if args.id.is_some() && !args.editor {
+        edit_prompt(store, &selected[0], args.force)
+    } else {
+        edit_in_editor(store, &selected, args.force)
+    }
+}
+
+fn edit_targets(store: &dyn BookmarkStore, args: &EditArgs) -> Result<Vec<Bookmark>> {
+    let selected: Vec<Bookmark> = match args.id {
+        Some(id) => vec![store.get(id)?],
+        None => store.all()?.into_iter()
+            .filter(|bm| args.tag.as_ref().is_none_or(|tag| bm.tags.contains(&tag.to_lowercase())))
+            .filter(|bm| args.folder.as_ref().is_none_or(|folder| &bm.folder == folder))
+            .collect(),
+    };
+
+    if selected.is_empty() {
+        return Err(BookmanError::NotFound("no bookmarks match the given filter".to_string()));
+    }
+
+    Ok(selected)
+}
+
+// Applies --star, --unstar and --rating, a rating of 0 removes it.
+fn rate(store: &dyn BookmarkStore, selected: &[Bookmark], args: &EditArgs) -> Result<()> {
+    for bookmark in selected {
+        store.update(&Bookmark {
+            starred: if args.star { true } else if args.unstar { false } else { bookmark.starred },
+            rating: match args.rating {
+                Some(0) => None,
+                Some(rating) => Some(rating),
+                None => bookmark.rating,
+            },
+            ..bookmark.clone()
+        })?;
+    }
+
+    Ok(())
+}
+
+fn edit_prompt(store: &dyn BookmarkStore, old: &Bookmark, force: bool) -> Result<()> {
+    let (new_name, new_url, new_description) = utils::prompt_user()?;
+
+    let name = if new_name.trim().is_empty() { old.name.to_string() } else { new_name.trim().to_string() };
+    let url = if new_url.trim().is_empty() { old.url.to_string() } else { utils::normalize_url(&new_url, force)? };
+    let description = if new_description.trim().is_empty() { old.description.to_string() } else { new_description.to_string() };
+
+    store.update(&Bookmark { name, url, description, ..old.clone() })?;
+
+    notify::send_notification(Event::Updated, "Bookmark is updated!");
+    #[cfg(debug_assertions)]
+    {
+        println!("Bookmark updated!");
+    }
+
+    Ok(())
+}
+
+pub(crate) fn edit_in_editor(store: &dyn BookmarkStore, bookmarks: &[Bookmark], force: bool) -> Result<()> {
+    let changed = editor::edit_bookmarks(bookmarks, force)?;
+    for bookmark in &changed {
+        store.update(bookmark)?;
+    }
+
+    notify::send_notification(Event::Updated, &format!("{} bookmarks are updated!", changed.len()));
+    #[cfg(debug_assertions)]
+    {
+        println!("{} bookmarks updated!", changed.len());
+    }
+
+    Ok(())
+}
+
+/// Removes the bookmark with the given ID, the bookmarks matching a query or, without either, the ones picked.
+pub fn remove(store: &dyn BookmarkStore, target: &str, yes: bool, menu: Picker) -> Result<()> {
+    if target.is_empty() {
+        return match picker::pick(filtered(store, "", Sort::Frecency)?, Action::Delete, menu)? {
+            Some((Action::Delete, selected)) => remove_bookmarks(store, &selected, yes),
+            Some((action, selected)) => run_action(store, action, &selected),
+            None => Ok(()),
+        };
+    }
+
+    let bookmarks = match target.trim().parse::<i32>() {
+        Ok(id) => vec![store.get(id)?],
+        Err(_) => filtered(store, target, Sort::Id)?,
+    };
+
+    if bookmarks.is_empty() {
+        return Err(BookmanError::NotFound("no bookmarks match the given query".to_string()));
+    }
+
+    remove_bookmarks(store, &bookmarks, yes)
+}
+
+fn remove_bookmarks(store: &dyn BookmarkStore, bookmarks: &[Bookmark], yes: bool) -> Result<()> {
+    for bookmark in bookmarks {
+        println!("{}", bookmark.format(config::DISPLAY_TEMPLATE));
+    }
+    // Declining, or no answer at all when stdin is closed, leaves the bookmarks where they are.
+    if !yes && !utils::confirm(&format!("Move {} bookmarks to the trash?", bookmarks.len())) {
+        eprintln!("Aborted, no bookmarks are removed");
+        return Ok(());
+    }
+
+    let removed = trash_bookmarks(store, bookmarks)?;
+
+    notify::send_notification(Event::Removed, &format!("{} bookmarks are moved to the trash!", removed));
+    #[cfg(debug_assertions)]
+    {
+        println!("{} bookmarks moved to the trash!", removed);
+    }
+
+    Ok(())
+}
+
+fn trash_bookmarks(store: &dyn BookmarkStore, bookmarks: &[Bookmark]) -> Result<usize> {
+    let mut removed = 0;
+    for bookmark in bookmarks {
+        match store.remove(bookmark.id) {
+            Ok(()) => {
+                removed += 1;
+            }
+            Err(BookmanError::NotFound(message)) => {
+                eprintln!("{}", message);
             }
+            Err(err) => {
+                return Err(err);
+            }
+        }
+    }
+
+    Ok(removed)
+}
+
+pub fn trash_list(store: &dyn BookmarkStore) -> Result<()> {
+    for bm in store.trash()? {
+        println!("{} | removed {}", bm.format(config::DISPLAY_TEMPLATE), entry::format_time(bm.deleted_at, "unknown"));
+    }
+
+    Ok(())
+}
+
+pub fn trash_restore(store: &dyn BookmarkStore, id: i32) -> Result<()> {
+    store.restore(id)?;
+    notify::send_notification(Event::Updated, "Bookmark is restored!");
+
+    Ok(())
+}
+
+pub fn trash_empty(store: &dyn BookmarkStore) -> Result<()> {
+    println!("{} bookmarks deleted for good", store.empty_trash()?);
+
+    Ok(())
+}
+
+pub fn history(store: &dyn BookmarkStore, id: Option<i32>, limit: usize) -> Result<()> {
+    for change in store.history(id, limit)? {
+        let name = change.after.as_ref().or(change.before.as_ref()).map_or("", |bm| bm.name.as_str());
+        let mut line = format!("{} | {} | {} | {} | {}",
+            change.id, entry::format_time(Some(change.at), "unknown"), change.op, change.bookmark_id, name);
+
+        if let (Some(before), Some(after)) = (&change.before, &change.after) && change.op == "update" {
+            line.push_str(&format!(" | {}", before.changed_fields(after).join(", ")));
         }
-        Err(err) => {
-            utils::sql_driver_error(err);
+        if let Some(undoes) = change.undoes {
+            line.push_str(&format!(" | undoes change {}", undoes));
         }
+        if change.undone {
+            line.push_str(" (undone)");
+        }
+
+        println!("{}", line);
+    }
+
+    Ok(())
+}
+
+pub fn undo(store: &dyn BookmarkStore, count: usize) -> Result<()> {
+    println!("{} changes reverted", store.undo(count)?);
+
+    Ok(())
+}
+
+pub fn clip(store: &dyn BookmarkStore, order: Sort, format: Option<LinkFormat>, wait: bool, menu: Picker) -> Result<()> {
+    let format = match format {
+        Some(format) => format,
+        None => LinkFormat::from_str(config::CLIP_FORMAT, true)
+            .map_err(|_| BookmanError::InvalidInput(format!("unknown clip format '{}'", config::CLIP_FORMAT)))?,
+    };
+    let bookmarks = filtered(store, "", order)?;
+
+    match picker::pick(bookmarks, Action::CopyUrl, menu)? {
+        Some((Action::CopyUrl, selected)) => copy(store, format, &selected, wait),
+        Some((Action::CopyMarkdown, selected)) => copy(store, LinkFormat::Markdown, &selected, wait),
+        Some((action, selected)) => run_action(store, action, &selected),
+        None => Ok(()),
     }
 }
 
-pub fn remove(conn: &Connection, id: i32) {
-    match db_driver::remove_entry(conn, id) {
-        Ok(()) => {
-            notify::send_notification("Bookman", "Bookmark is removed!");
+pub fn tui(store: &dyn BookmarkStore) -> Result<()> {
+    tui::run(store)
+}
+
+pub fn hold_clipboard(html: bool, keep: bool) -> Result<()> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+
+    // With --html the plain text and the HTML are separated by a NUL.
+    let (text, links) = match input.split_once('\0') {
+        Some((text, links)) if html => (text, Some(links)),
+        _ => (input.as_str(), None),
+    };
+
+    if keep {
+        clipboard::keep(text, links)
+    } else {
+        clipboard::copy(text, links)
+    }
+}
+
+pub fn import(store: &dyn BookmarkStore, source: &str) -> Result<()> {
+    let imported = if source.to_lowercase().ends_with(".json") {
+        parser::parse_json_bookmarks(source)?
+    } else {
+        parser::parse_bookmarks(source)?
+    };
+
+    for bm in imported {
+        store.add(&bm)?;
+    }
+
+    Ok(())
+}
+
+pub fn export(store: &dyn BookmarkStore, query: &str, format: Format, output: Option<&str>) -> Result<()> {
+    write_export(&filtered(store, query, Sort::Id)?, "Bookmarks", format, output)
+}
+
+fn write_export(bookmarks: &[Bookmark], title: &str, format: Format, output: Option<&str>) -> Result<()> {
+    let text = match format {
+        Format::Html => export::to_html(bookmarks, title),
+        Format::Markdown => export::to_markdown(bookmarks, title),
+        Format::Json => export::to_json(bookmarks),
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(path, text)?;
             #[cfg(debug_assertions)]
             {
-                println!("Bookmark removed!");
+                println!("{} bookmarks exported to {}", bookmarks.len(), path);
             }
         }
-        Err(err) => {
-            utils::sql_driver_error(err);
+        None => {
+            print!("{}", text);
         }
     }
+
+    Ok(())
 }
 
-pub fn clip(conn: &Connection) {
-    if let Some(url) = search(conn) {
-        notify::send_notification("Bookman", &format!("Copied to clipboard!: {}", url));
-        utils::copy_to_clipboard(&url);
+pub fn collection_add(store: &dyn BookmarkStore, name: &str, query: &str) -> Result<()> {
+    store.add_collection(name, query)?;
+    notify::send_notification(Event::Saved, &format!("Collection {} is saved!", name));
+
+    Ok(())
+}
+
+pub fn collection_list(store: &dyn BookmarkStore) -> Result<()> {
+    for (name, query) in store.collections()? {
+        println!("{} | {}", name, query);
     }
+
+    Ok(())
 }
 
-pub fn import(conn: &Connection, source: &str) {
-    match parser::parse_bookmarks(source) {
-        Ok(imported) => {
-            for bm in imported {
-                match db_driver::insert_entry(conn, &bm.name, &bm.url, &bm.description) {
-                    Ok(()) => {
-                        continue;
-                    }
-                    Err(err) => {
-                        utils::die("Import error", err);
+pub fn collection_remove(store: &dyn BookmarkStore, name: &str) -> Result<()> {
+    store.remove_collection(name)?;
+    notify::send_notification(Event::Removed, &format!("Collection {} is removed!", name));
 
-                    }
-                }
-            }
+    Ok(())
+}
+
+pub fn collection_export(store: &dyn BookmarkStore, name: &str, format: Format, output: Option<&str>) -> Result<()> {
+    let query = scope(store, Some(name), "")?;
+    write_export(&filtered(store, &query, Sort::Id)?, name, format, output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_store::MemoryStore;
+    use crate::parser::{Cli, Commands};
+    use clap::Parser;
+    use std::env;
+    use std::process;
+
+    fn add_args(args: &[&str]) -> AddArgs {
+        match Cli::try_parse_from([&["bookman", "add", "--no-fetch"], args].concat()).unwrap().command {
+            Commands::Add(args) => args,
+            _ => unreachable!(),
         }
-        Err(err) => {
-            utils::parser_error(err);
+    }
+
+    fn edit_args(args: &[&str]) -> EditArgs {
+        match Cli::try_parse_from([&["bookman", "edit"], args].concat()).unwrap().command {
+            Commands::Edit(args) => args,
+            _ => unreachable!(),
         }
     }
+
+    fn sample_store() -> MemoryStore {
+        let store = MemoryStore::new();
+        add_url(&store, "https://example.com/a", &add_args(&["-n", "A", "-t", "work"]), State::Read).unwrap();
+        add_url(&store, "https://example.com/b", &add_args(&["-n", "B", "-t", "work,old"]), State::Read).unwrap();
+        add_url(&store, "https://example.org/c", &add_args(&["-n", "C", "-t", "old"]), State::Unread).unwrap();
+        store
+    }
+
+    #[test]
+    fn adds_normalized_urls_with_clean_tags() {
+        let store = MemoryStore::new();
+        let args = add_args(&["-t", "Rust, docs,rust", "--folder", "Dev"]);
+
+        let bookmark = store.get(add_url(&store, "example.com/docs", &args, State::Unread).unwrap()).unwrap();
+        assert_eq!(bookmark.url, "https://example.com/docs");
+        assert_eq!(bookmark.name, "https://example.com/docs");
+        assert_eq!(bookmark.tags, vec!["docs".to_string(), "rust".to_string()]);
+        assert_eq!(bookmark.folder, "Dev");
+        assert_eq!(bookmark.state, State::Unread);
+
+        assert!(matches!(add_url(&store, "not a url", &args, State::Read), Err(BookmanError::InvalidInput(_))));
+        assert_eq!(store.all().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rates_every_bookmark_with_a_tag() {
+        let store = sample_store();
+
+        let args = edit_args(&["--tag", "work", "--star", "--rating", "4"]);
+        rate(&store, &edit_targets(&store, &args).unwrap(), &args).unwrap();
+        assert_eq!(filtered(&store, "is:starred rating:4", Sort::Id).unwrap().len(), 2);
+
+        let args = edit_args(&["1", "--rating", "0"]);
+        rate(&store, &edit_targets(&store, &args).unwrap(), &args).unwrap();
+        assert_eq!(store.get(1).unwrap().rating, None);
+        assert!(store.get(1).unwrap().starred);
+
+        assert!(matches!(edit_targets(&store, &edit_args(&["--tag", "none", "--star"])), Err(BookmanError::NotFound(_))));
+    }
+
+    #[test]
+    fn removed_bookmarks_go_to_the_trash() {
+        let store = sample_store();
+        let old = filtered(&store, "tag:old", Sort::Id).unwrap();
+
+        assert_eq!(trash_bookmarks(&store, &old).unwrap(), 2);
+        assert_eq!(trash_bookmarks(&store, &old).unwrap(), 0);
+        assert_eq!(store.all().unwrap().len(), 1);
+        assert_eq!(store.trash().unwrap().len(), 2);
+
+        store.restore(old[0].id).unwrap();
+        assert_eq!(filtered(&store, "tag:old", Sort::Id).unwrap().len(), 1);
+        assert_eq!(store.empty_trash().unwrap(), 1);
+        assert!(store.trash().unwrap().is_empty());
+    }
+
+    #[test]
+    fn undo_is_appended_to_the_history() {
+        let store = sample_store();
+        store.remove(1).unwrap();
+
+        assert_eq!(store.undo(1).unwrap(), 4);
+        assert!(store.all().unwrap().is_empty());
+
+        let history = store.history(None, 10).unwrap();
+        assert_eq!(history.len(), 8);
+        assert_eq!((history[0].op.as_str(), history[0].undoes), ("undo", Some(history[7].id)));
+        assert!(history[0].after.is_none() && history[0].before.is_some());
+        assert!(history[4..].iter().all(|change| change.undone));
+        assert!(matches!(store.undo(1), Err(BookmanError::NotFound(_))));
+    }
+
+    #[test]
+    fn imports_exported_bookmarks() {
+        let source = sample_store();
+        source.update(&Bookmark { starred: true, folder: "Dev".to_string(), ..source.get(2).unwrap() }).unwrap();
+        let path = env::temp_dir().join(format!("bookman-import-{}.json", process::id()));
+        fs::write(&path, export::to_json(&source.all().unwrap())).unwrap();
+
+        let store = MemoryStore::new();
+        let imported = import(&store, path.to_str().unwrap());
+        let _ = fs::remove_file(&path);
+        imported.unwrap();
+
+        let bookmarks = store.all().unwrap();
+        assert_eq!(bookmarks.len(), 3);
+        assert_eq!(bookmarks[1].name, "B");
+        assert_eq!(bookmarks[1].tags, vec!["old".to_string(), "work".to_string()]);
+        assert_eq!(bookmarks[1].folder, "Dev");
+        assert!(bookmarks[1].starred);
+        assert_eq!(bookmarks[2].state, State::Unread);
+    }
 }
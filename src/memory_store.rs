@@ -0,0 +1,355 @@
+/*
+    This file is a part of bookman software.
+
+    It contains MemoryStore, a BookmarkStore that keeps everything in memory.
+    It is used for testing and by programs that don't need a database file.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use crate::entry::{Bookmark, Change, State};
+use crate::error::{BookmanError, Result};
+use crate::query::Query;
+use crate::store::BookmarkStore;
+use crate::utils;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Default)]
+pub struct MemoryStore {
+    inner: RefCell<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    bookmarks: BTreeMap<i32, Bookmark>,
+    last_id: i32,
+    // (bookmark ID, kind, timestamp)
+    events: Vec<(i32, String, i64)>,
+    collections: BTreeMap<String, String>,
+    history: Vec<Change>,
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
+
+impl MemoryStore {
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+}
+
+impl Inner {
+    // Mirrors the frecency subquery of the SQLite store.
+    fn with_frecency(&self, bookmark: &Bookmark) -> Bookmark {
+        let now = now();
+        let frecency = self.events.iter()
+            .filter(|(id, _, _)| *id == bookmark.id)
+            .map(|(_, kind, at)| {
+                let weight = if kind == "open" { 1.0 } else { 0.5 };
+                let age = (now - at) / (24 * 3600);
+                let bucket = match age {
+                    ..4 => 100.0,
+                    4..14 => 70.0,
+                    14..31 => 50.0,
+                    31..90 => 30.0,
+                    _ => 10.0,
+                };
+                weight * bucket
+            })
+            .sum();
+
+        Bookmark { frecency, ..bookmark.clone() }
+    }
+
+    fn live(&self) -> impl Iterator<Item = Bookmark> + '_ {
+        self.bookmarks.values().filter(|bm| bm.deleted_at.is_none()).map(|bm| self.with_frecency(bm))
+    }
+
+    fn record(&mut self, op: &str, id: i32, before: Option<Bookmark>, after: Option<Bookmark>) {
+        if let (Some(before), Some(after)) = (&before, &after) && before.changed_fields(after).is_empty()
+            && before.deleted_at == after.deleted_at {
+            return;
+        }
+
+        self.append(op, id, before, after, None);
+    }
+
+    // Like the SQLite store the history is append-only, an undo is a new entry pointing at the change.
+    fn append(&mut self, op: &str, id: i32, before: Option<Bookmark>, after: Option<Bookmark>, undoes: Option<i64>) {
+        self.history.push(Change {
+            id: self.history.len() as i64 + 1,
+            // Like the SQLite store, everything done while the store is open is a single batch.
+            batch: 1,
+            op: op.to_string(),
+            bookmark_id: id,
+            before,
+            after,
+            at: now(),
+            undone: false,
+            undoes,
+        });
+    }
+
+    // Applies a change to a stored bookmark and records it, does nothing for unknown IDs.
+    fn change(&mut self, op: &str, id: i32, apply: impl FnOnce(&mut Bookmark)) {
+        if let Some(bookmark) = self.bookmarks.get_mut(&id) {
+            let before = bookmark.clone();
+            apply(bookmark);
+            let after = bookmark.clone();
+            self.record(op, id, Some(before), Some(after));
+        }
+    }
+
+    fn undone(&self, change: &Change) -> bool {
+        self.history.iter().any(|other| other.undoes == Some(change.id))
+    }
+
+    fn revert(&mut self, change: &Change) -> Result<()> {
+        let id = change.bookmark_id;
+
+        match (change.op.as_str(), &change.before) {
+            ("insert", _) => {
+                self.bookmarks.remove(&id);
+                self.events.retain(|(bookmark_id, _, _)| *bookmark_id != id);
+            }
+            ("update", Some(before)) => {
+                if let Some(bookmark) = self.bookmarks.get_mut(&id) {
+                    write_entry(bookmark, before);
+                }
+            }
+            ("delete" | "restore", Some(before)) => {
+                if let Some(bookmark) = self.bookmarks.get_mut(&id) {
+                    bookmark.deleted_at = before.deleted_at;
+                }
+            }
+            ("purge", Some(before)) => {
+                self.bookmarks.entry(id).or_insert_with(|| before.clone());
+            }
+            _ => {
+                return Err(BookmanError::InvalidInput(format!("change {} ({}) can't be undone", change.id, change.op)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Copies the fields the user can edit, like `UPDATE bookmarks` does.
+fn write_entry(bookmark: &mut Bookmark, from: &Bookmark) {
+    let mut tags = from.tags.clone();
+    tags.sort();
+    tags.dedup();
+
+    bookmark.name = from.name.trim().to_string();
+    bookmark.url = from.url.trim().to_string();
+    bookmark.description = from.description.trim().to_string();
+    bookmark.folder = from.folder.trim().to_string();
+    bookmark.state = from.state;
+    bookmark.starred = from.starred;
+    bookmark.rating = from.rating;
+    bookmark.tags = tags;
+}
+
+impl BookmarkStore for MemoryStore {
+    fn add(&self, bookmark: &Bookmark) -> Result<i32> {
+        let mut inner = self.inner.borrow_mut();
+        inner.last_id += 1;
+
+        let mut new = Bookmark {
+            id: inner.last_id,
+            added: Some(bookmark.added.unwrap_or_else(now)),
+            link_status: bookmark.link_status,
+            snippet: bookmark.snippet.clone(),
+            ..Default::default()
+        };
+        write_entry(&mut new, &Bookmark { tags: utils::clean_tags(&bookmark.tags), ..bookmark.clone() });
+
+        let id = new.id;
+        inner.bookmarks.insert(id, new.clone());
+        inner.record("insert", id, None, Some(new));
+
+        Ok(id)
+    }
+
+    fn get(&self, id: i32) -> Result<Bookmark> {
+        self.inner.borrow().live()
+            .find(|bm| bm.id == id)
+            .ok_or_else(|| BookmanError::NotFound(format!("bookmark {} not found", id)))
+    }
+
+    fn all(&self) -> Result<Vec<Bookmark>> {
+        Ok(self.inner.borrow().live().collect())
+    }
+
+    fn filter(&self, query: &Query) -> Result<Vec<Bookmark>> {
+        Ok(self.inner.borrow().live().filter(|bm| query.matches(bm)).collect())
+    }
+
+    fn update(&self, bookmark: &Bookmark) -> Result<()> {
+        self.inner.borrow_mut().change("update", bookmark.id, |bm| write_entry(bm, bookmark));
+
+        Ok(())
+    }
+
+    fn set_tags(&self, id: i32, tags: &[String]) -> Result<()> {
+        self.inner.borrow_mut().change("update", id, |bm| bm.tags = utils::clean_tags(tags));
+
+        Ok(())
+    }
+
+    fn set_state(&self, id: i32, state: State) -> Result<()> {
+        self.inner.borrow_mut().change("update", id, |bm| bm.state = state);
+
+        Ok(())
+    }
+
+    fn record_visit(&self, id: i32) -> Result<()> {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(bookmark) = inner.bookmarks.get_mut(&id) {
+            bookmark.visits += 1;
+            bookmark.last_visited = Some(now());
+        }
+        inner.events.push((id, "open".to_string(), now()));
+
+        Ok(())
+    }
+
+    fn record_event(&self, id: i32, kind: &str) -> Result<()> {
+        self.inner.borrow_mut().events.push((id, kind.to_string(), now()));
+
+        Ok(())
+    }
+
+    fn remove(&self, id: i32) -> Result<()> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.bookmarks.get(&id).is_none_or(|bm| bm.deleted_at.is_some()) {
+            return Err(BookmanError::NotFound(format!("bookmark {} not found", id)));
+        }
+
+        inner.change("delete", id, |bm| bm.deleted_at = Some(now()));
+
+        Ok(())
+    }
+
+    fn trash(&self) -> Result<Vec<Bookmark>> {
+        let inner = self.inner.borrow();
+        let mut trash: Vec<Bookmark> = inner.bookmarks.values()
+            .filter(|bm| bm.deleted_at.is_some())
+            .map(|bm| inner.with_frecency(bm))
+            .collect();
+        trash.sort_by_key(|bm| std::cmp::Reverse(bm.deleted_at));
+
+        Ok(trash)
+    }
+
+    fn restore(&self, id: i32) -> Result<()> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.bookmarks.get(&id).is_none_or(|bm| bm.deleted_at.is_none()) {
+            return Err(BookmanError::NotFound(format!("bookmark {} is not in the trash", id)));
+        }
+
+        inner.change("restore", id, |bm| bm.deleted_at = None);
+
+        Ok(())
+    }
+
+    fn empty_trash(&self) -> Result<usize> {
+        let mut inner = self.inner.borrow_mut();
+        let trashed: Vec<Bookmark> = inner.bookmarks.values().filter(|bm| bm.deleted_at.is_some()).cloned().collect();
+
+        for bookmark in &trashed {
+            inner.bookmarks.remove(&bookmark.id);
+            inner.events.retain(|(id, _, _)| *id != bookmark.id);
+            inner.record("purge", bookmark.id, Some(bookmark.clone()), None);
+        }
+
+        Ok(trashed.len())
+    }
+
+    fn history(&self, id: Option<i32>, limit: usize) -> Result<Vec<Change>> {
+        let inner = self.inner.borrow();
+
+        Ok(inner.history.iter().rev()
+            .filter(|change| id.is_none_or(|id| change.bookmark_id == id))
+            .take(limit)
+            .map(|change| Change { undone: inner.undone(change), ..change.clone() })
+            .collect())
+    }
+
+    fn undo(&self, count: usize) -> Result<usize> {
+        let mut inner = self.inner.borrow_mut();
+        let pending = |inner: &Inner, change: &Change| !["expire", "undo"].contains(&change.op.as_str()) && !inner.undone(change);
+
+        let mut batches: Vec<i64> = inner.history.iter()
+            .filter(|change| pending(&inner, change))
+            .map(|change| change.batch)
+            .collect();
+        batches.sort();
+        batches.dedup();
+        let batches: Vec<i64> = batches.into_iter().rev().take(count).collect();
+
+        let changes: Vec<Change> = inner.history.iter().rev()
+            .filter(|change| pending(&inner, change) && batches.contains(&change.batch))
+            .cloned()
+            .collect();
+
+        for change in &changes {
+            let before = inner.bookmarks.get(&change.bookmark_id).cloned();
+            inner.revert(change)?;
+            let after = inner.bookmarks.get(&change.bookmark_id).cloned();
+            inner.append("undo", change.bookmark_id, before, after, Some(change.id));
+        }
+
+        match changes.len() {
+            0 => Err(BookmanError::NotFound("there is nothing to undo".to_string())),
+            reverted => Ok(reverted),
+        }
+    }
+
+    fn add_collection(&self, name: &str, query: &str) -> Result<()> {
+        Query::parse(query)?;
+
+        let mut inner = self.inner.borrow_mut();
+        if inner.collections.contains_key(name) {
+            return Err(BookmanError::Duplicate(format!("collection '{}' already exists", name)));
+        }
+        inner.collections.insert(name.to_string(), query.to_string());
+
+        Ok(())
+    }
+
+    fn collection(&self, name: &str) -> Result<String> {
+        self.inner.borrow().collections.get(name).cloned()
+            .ok_or_else(|| BookmanError::NotFound(format!("collection '{}' does not exist", name)))
+    }
+
+    fn collections(&self) -> Result<Vec<(String, String)>> {
+        Ok(self.inner.borrow().collections.iter().map(|(name, query)| (name.clone(), query.clone())).collect())
+    }
+
+    fn remove_collection(&self, name: &str) -> Result<()> {
+        match self.inner.borrow_mut().collections.remove(name) {
+            Some(_) => Ok(()),
+            None => Err(BookmanError::NotFound(format!("collection '{}' does not exist", name))),
+        }
+    }
+}
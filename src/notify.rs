@@ -2,6 +2,8 @@
     This file is a part of bookman software.
 
     This module contains helper functions respobnsible for notifications.
+    They are sent to the sink picked by config::NOTIFY and never fail the
+    command: when the desktop can't be reached they are printed to stderr.
 
     Copyright (c) 2025 Pavel Pleskunov.
 
@@ -21,29 +23,178 @@
     USA
 */
 
+use crate::config;
+use crate::error::{BookmanError, Result};
+
 #[cfg(target_os = "linux")]
-use notify_rust::Notification;
+use notify_rust::{Notification, Timeout, Urgency};
 
 #[cfg(target_os = "macos")]
 use std::process::Command;
 
-pub fn send_notification(title: &str, message: &str) {
+const TITLE: &str = "Bookman";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// Bookmarks or collections are added.
+    Saved,
+    /// Bookmarks are edited, tagged, archived or restored.
+    Updated,
+    /// Bookmarks are copied to the clipboard.
+    Copied,
+    /// Bookmarks are moved to the trash or collections are removed.
+    Removed,
+    /// The unread count sent by `queue --notify`.
+    Queue,
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Saved => "saved",
+            Event::Updated => "updated",
+            Event::Copied => "copied",
+            Event::Removed => "removed",
+            Event::Queue => "queue",
+        }
+    }
+}
+
+pub trait Sink {
+    fn send(&self, title: &str, message: &str) -> Result<()>;
+
+    /// Sends a notification with buttons, given as (identifier, label), and
+    /// waits for one of them. Sinks without buttons just send the message.
+    fn ask(&self, title: &str, message: &str, _actions: &[(&str, &str)]) -> Result<Option<String>> {
+        self.send(title, message)?;
+
+        Ok(None)
+    }
+}
+
+pub struct Desktop;
+pub struct Stderr;
+pub struct Silent;
+
+#[cfg(target_os = "linux")]
+fn notification(title: &str, message: &str) -> Notification {
+    let urgency = match config::NOTIFY_URGENCY {
+        "low" => Urgency::Low,
+        "critical" => Urgency::Critical,
+        _ => Urgency::Normal,
+    };
+    let timeout = match config::NOTIFY_TIMEOUT_MS {
+        ..0 => Timeout::Default,
+        0 => Timeout::Never,
+        ms => Timeout::Milliseconds(ms as u32),
+    };
+
+    let mut notification = Notification::new();
+    notification
+        .summary(title)
+        .body(message)
+        .icon("dialog-information") // Use a system icon or specify your own
+        .urgency(urgency)
+        .timeout(timeout);
+    notification
+}
+
+#[cfg(target_os = "linux")]
+fn show_error(err: notify_rust::error::Error) -> BookmanError {
+    BookmanError::External(format!("failed to send notification: {}", err))
+}
+
+impl Sink for Desktop {
+    #[allow(unused_variables)]
+    fn send(&self, title: &str, message: &str) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            notification(title, message).show().map_err(show_error)?;
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            let quote = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+            let status = Command::new("osascript")
+                .arg("-e")
+                .arg(format!("display notification \"{}\" with title \"{}\"", quote(message), quote(title)))
+                .output()?
+                .status;
+            if !status.success() {
+                return Err(BookmanError::External("failed to send notification".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
     #[cfg(target_os = "linux")]
-    {
-        Notification::new()
-            .summary(title)
-            .body(message)
-            .icon("dialog-information") // Use a system icon or specify your own
-            .show()
-            .expect("Failed to send notification");
-    }
-
-    #[cfg(target_os = "macos")]
-    {
-        Command::new("osascript")
-            .arg("-e")
-            .arg(format!("display notification \"{}\" with title \"{}\"", message, title))
-            .output()
-            .expect("Failed to send notification");
+    fn ask(&self, title: &str, message: &str, actions: &[(&str, &str)]) -> Result<Option<String>> {
+        let mut notification = notification(title, message);
+        for (identifier, label) in actions {
+            notification.action(identifier, label);
+        }
+
+        let mut chosen = None;
+        notification.show().map_err(show_error)?.wait_for_action(|action| {
+            if action != "__closed" {
+                chosen = Some(action.to_string());
+            }
+        });
+
+        Ok(chosen)
+    }
+}
+
+impl Sink for Stderr {
+    fn send(&self, title: &str, message: &str) -> Result<()> {
+        eprintln!("{}: {}", title, message);
+
+        Ok(())
+    }
+}
+
+impl Sink for Silent {
+    fn send(&self, _title: &str, _message: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The sink selected by config::NOTIFY, unknown values fall back to stderr.
+pub fn sink() -> Box<dyn Sink> {
+    match config::NOTIFY {
+        "desktop" => Box::new(Desktop),
+        "none" => Box::new(Silent),
+        _ => Box::new(Stderr),
+    }
+}
+
+pub fn send_notification(event: Event, message: &str) {
+    ask(event, message, &[]);
+}
+
+/// Sends a notification with buttons, given as (identifier, label), and
+/// returns the identifier of the one that was clicked.
+pub fn ask(event: Event, message: &str, actions: &[(&str, &str)]) -> Option<String> {
+    if !config::NOTIFY_EVENTS.contains(&event.name()) {
+        return None;
+    }
+
+    let sink = sink();
+    let sent = match actions {
+        [] => sink.send(TITLE, message).map(|_| None),
+        actions => sink.ask(TITLE, message, actions),
+    };
+
+    match sent {
+        Ok(chosen) => chosen,
+        Err(_err) => {
+            #[cfg(debug_assertions)]
+            {
+                println!("Notification failed: {}", _err);
+            }
+            let _ = Stderr.send(TITLE, message);
+            None
+        }
     }
 }
@@ -23,16 +23,19 @@
 */
 
 use crate::entry::Bookmark;
+use crate::error::{BookmanError, Result};
 use std::fs;
-use std::io;
-use scraper::{Html, Selector};
-use clap::{Parser, Subcommand};
-use std::error::Error;
+use scraper::{ElementRef, Html, Selector};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 /// CLI Bookmark Manager with encryption
 #[derive(Parser)]
 #[command(name = "bookman", version = "1.0", author = "Pavel", about = "A simple CLI Bookmark Manager")]
 pub struct Cli {
+    /// Menu used to pick bookmarks (PICKER from config.rs if omitted)
+    #[arg(long, global = true, value_enum)]
+    pub picker: Option<Picker>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -40,70 +43,490 @@ pub struct Cli {
 #[derive(Subcommand)]
 pub enum Commands {
     /// Add a new bookmark
-    Add {
-        /// Copy from clipboard
+    Add(AddArgs),
+
+    /// Add a bookmark to the read-later queue
+    Later(AddArgs),
+
+    /// List unread bookmarks, oldest first
+    Queue {
+        /// Send a notification with the number of unread bookmarks instead
         #[arg(short, long)]
-        clipboard: bool,
+        notify: bool,
     },
 
-    /// Search bookmarks
-    Search,
+    /// Search bookmarks and open the selected one
+    Search {
+        /// Order of the bookmarks in the picker
+        #[arg(short, long, value_enum, default_value_t = Sort::Frecency)]
+        sort: Sort,
 
-    /// Edit a bookmark
-    Edit {
-        /// ID of the bookmark to edit
-        id: i32,
+        /// Limit the picker to a saved collection
+        #[arg(short, long)]
+        collection: Option<String>,
+
+        /// Query narrowing down the bookmarks shown in the picker
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+
+    /// Open a bookmark in the browser
+    Open {
+        /// Order of the bookmarks in the picker
+        #[arg(short, long, value_enum, default_value_t = Sort::Frecency)]
+        sort: Sort,
+
+        /// Limit the picker to a saved collection
+        #[arg(short, long)]
+        collection: Option<String>,
+
+        /// Query narrowing down the bookmarks shown in the picker
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+
+    /// List bookmarks
+    List {
+        /// Order of the bookmarks
+        #[arg(short, long, value_enum, default_value_t = Sort::Id)]
+        sort: Sort,
+
+        /// Limit the list to a saved collection
+        #[arg(short, long)]
+        collection: Option<String>,
+
+        /// Query the bookmarks have to match
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+
+    /// Print URLs of the bookmarks matching a query
+    Find {
+        /// Order of the bookmarks
+        #[arg(short, long, value_enum, default_value_t = Sort::Frecency)]
+        sort: Sort,
+
+        /// Query the bookmarks have to match, e.g. 'tag:rust domain:github.com -tag:archived "async runtime"'
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        query: Vec<String>,
     },
 
-    /// Remove a bookmark
+    /// Edit a bookmark
+    Edit(EditArgs),
+
+    /// Move bookmarks to the trash
     Remove {
-        /// ID of the bookmark to remove
-        id: i32,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+
+        /// ID of the bookmark or a query the removed bookmarks have to match (picker if omitted)
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        target: Vec<String>,
     },
 
     /// Copy bookmark URL to clipboard
-    Clip,
+    Clip {
+        /// Order of the bookmarks in the picker
+        #[arg(short, long, value_enum, default_value_t = Sort::Frecency)]
+        sort: Sort,
+
+        /// Return right away and clear the clipboard in the background
+        #[arg(long)]
+        no_wait: bool,
+
+        /// How the bookmarks are copied (CLIP_FORMAT from config.rs if omitted)
+        #[arg(long = "as", value_enum)]
+        format: Option<LinkFormat>,
+    },
+
+    /// Browse and manage bookmarks in a full-screen interface
+    Tui,
+
+    /// Keep the text read from stdin in the clipboard, used by `clip --no-wait` and on X11
+    #[command(hide = true)]
+    HoldClipboard {
+        /// The text is HTML
+        #[arg(long)]
+        html: bool,
+
+        /// Keep serving the text until something else is copied instead of clearing it
+        #[arg(long)]
+        keep: bool,
+    },
 
     /// Import bookmarks from a file
     Import {
         /// Path to the bookmarks file
         path: String,
     },
+
+    /// Export bookmarks to a file
+    Export {
+        /// Format of the exported file
+        #[arg(short, long, value_enum, default_value_t = Format::Html)]
+        format: Format,
+
+        /// Path to the exported file (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Query the exported bookmarks have to match
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+
+    /// Manage saved searches
+    Collection {
+        #[command(subcommand)]
+        command: CollectionCommands,
+    },
+
+    /// Manage removed bookmarks
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommands,
+    },
+
+    /// Show recent changes to bookmarks
+    History {
+        /// Only show changes of the bookmark with this ID
+        id: Option<i32>,
+
+        /// Number of changes to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Revert the changes made by the last commands
+    Undo {
+        /// Number of commands to revert
+        #[arg(default_value_t = 1)]
+        count: usize,
+    },
 }
 
-pub fn parse_bookmarks(html_file: &str) -> io::Result<Vec<Bookmark>> {
-    let html = fs::read_to_string(html_file)?;
-    let document = Html::parse_document(&html);
+#[derive(Subcommand)]
+pub enum CollectionCommands {
+    /// Save a query as a named collection
+    Add {
+        /// Name of the collection
+        name: String,
+
+        /// Query of the collection, e.g. 'tag:work -tag:done'
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        query: Vec<String>,
+    },
+
+    /// List saved collections
+    List,
+
+    /// Remove a collection
+    Remove {
+        /// Name of the collection
+        name: String,
+    },
+
+    /// Export the bookmarks of a collection to a file
+    Export {
+        /// Name of the collection
+        name: String,
+
+        /// Format of the exported file
+        #[arg(short, long, value_enum, default_value_t = Format::Html)]
+        format: Format,
+
+        /// Path to the exported file (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TrashCommands {
+    /// List removed bookmarks
+    List,
 
+    /// Restore a removed bookmark
+    Restore {
+        /// ID of the bookmark to restore
+        id: i32,
+    },
+
+    /// Delete all removed bookmarks for good
+    Empty,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// Netscape bookmark file understood by browsers
+    Html,
+    /// Markdown list of links
+    Markdown,
+    /// JSON with all bookmark fields, can be imported again
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum Picker {
+    /// Built-in picker, needs a terminal
+    Skim,
+    Dmenu,
+    Rofi,
+    Fuzzel,
+    Wofi,
+    /// Supports the same action keys as skim
+    Fzf,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum LinkFormat {
+    /// The URL
+    Plain,
+    /// [name](url)
+    Markdown,
+    /// <a href="url">name</a>, copied as rich text where the clipboard supports it
+    Html,
+    /// [[url][name]]
+    Org,
+    /// `name <url>`_
+    Rst,
+    /// The name
+    Title,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq)]
+pub enum Sort {
+    /// Oldest bookmarks first
+    Id,
+    /// Alphabetically by name
+    Name,
+    /// Most recently added first
+    Added,
+    /// Most frequently and recently used first
+    Frecency,
+    /// Highest rated first, ties broken by favorites
+    Rating,
+}
+
+#[derive(Args, Default)]
+pub struct AddArgs {
+    /// URL of the bookmark, or '-' to read URLs from stdin
+    #[arg(conflicts_with = "clipboard")]
+    pub url: Option<String>,
+
+    /// Name of the bookmark
+    #[arg(short, long)]
+    pub name: Option<String>,
+
+    /// Description of the bookmark
+    #[arg(short, long)]
+    pub description: Option<String>,
+
+    /// Tags of the bookmark (comma separated or repeated)
+    #[arg(short, long, value_delimiter = ',')]
+    pub tag: Vec<String>,
+
+    /// Folder of the bookmark
+    #[arg(long)]
+    pub folder: Option<String>,
+
+    /// Do not fetch missing name and description from the page
+    #[arg(long)]
+    pub no_fetch: bool,
+
+    /// Copy from clipboard
+    #[arg(short, long)]
+    pub clipboard: bool,
+
+    /// Mark the bookmark as a favorite
+    #[arg(long)]
+    pub star: bool,
+
+    /// Rate the bookmark from 1 to 5
+    #[arg(short, long, value_parser = clap::value_parser!(u8).range(1..=5))]
+    pub rating: Option<u8>,
+
+    /// Store the URL without validating it
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct EditArgs {
+    /// ID of the bookmark to edit
+    #[arg(required_unless_present_any = ["tag", "folder"])]
+    pub id: Option<i32>,
+
+    /// Edit the bookmark in $EDITOR
+    #[arg(short, long)]
+    pub editor: bool,
+
+    /// Edit all bookmarks with this tag in $EDITOR
+    #[arg(short, long, conflicts_with = "id")]
+    pub tag: Option<String>,
+
+    /// Edit all bookmarks in this folder in $EDITOR
+    #[arg(long, conflicts_with = "id")]
+    pub folder: Option<String>,
+
+    /// Mark the bookmarks as favorites
+    #[arg(long, conflicts_with = "unstar")]
+    pub star: bool,
+
+    /// Unmark the bookmarks as favorites
+    #[arg(long)]
+    pub unstar: bool,
+
+    /// Rate the bookmarks from 1 to 5, 0 removes the rating
+    #[arg(short, long, value_parser = clap::value_parser!(u8).range(0..=5))]
+    pub rating: Option<u8>,
+
+    /// Store the URL without validating it
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+/// Joins query words split by the shell, quoting the ones that were quoted phrases.
+pub fn join_query(words: &[String]) -> String {
+    words.iter()
+        .map(|word| {
+            if word.contains(char::is_whitespace) && !word.contains('"') {
+                match word.split_once(':') {
+                    Some((field, value)) if !field.contains(char::is_whitespace) => format!("{}:\"{}\"", field, value),
+                    _ => format!("\"{}\"", word),
+                }
+            } else {
+                word.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Reads a Netscape bookmark file as exported by Firefox, Chrome and bookman itself.
+pub fn parse_bookmarks(html_file: &str) -> Result<Vec<Bookmark>> {
+    Ok(parse_netscape(&fs::read_to_string(html_file)?))
+}
+
+pub fn parse_netscape(html: &str) -> Vec<Bookmark> {
+    let document = Html::parse_document(html);
     let a_selector = Selector::parse("dt > a").unwrap();
 
     let mut bookmarks = Vec::new();
 
-    let id = 0;
-    let description = String::new();
-
     for element in document.select(&a_selector) {
         let url = element.value().attr("href").unwrap_or("").to_string();
-        let name = element.text().collect::<Vec<_>>().join(" ");
-        /*
-        let _timestamp = element
-            .value()
-            .attr("add_date")
-            .and_then(|s| s.parse::<u64>().ok());
-        */
-        bookmarks.push(Bookmark { id : id.clone(), name, url, description : description.clone() });
+        let name = element.text().collect::<Vec<_>>().join(" ").trim().to_string();
+
+        let added = element.value().attr("add_date").and_then(|s| s.parse::<i64>().ok());
+        let tags = element.value().attr("tags")
+            .map(|tags| tags.split(',').map(|t| t.to_string()).collect())
+            .unwrap_or_default();
+
+        bookmarks.push(Bookmark {
+            name,
+            url,
+            description: netscape_description(element),
+            tags,
+            folder: netscape_folder(element),
+            added,
+            ..Default::default()
+        });
+    }
+
+    bookmarks
+}
+
+// Every <DL> a link is nested in follows the <H3> of its folder, nested folders are joined with '/'.
+fn netscape_folder(link: ElementRef) -> String {
+    let mut folders: Vec<String> = link.ancestors()
+        .filter_map(ElementRef::wrap)
+        .filter(|element| element.value().name() == "dl")
+        .filter_map(|list| list.prev_siblings().filter_map(ElementRef::wrap).next())
+        .filter(|heading| heading.value().name() == "h3")
+        .map(|heading| heading.text().collect::<String>().trim().to_string())
+        .collect();
+    folders.reverse();
+
+    folders.join("/")
+}
+
+// The description is a <DD> right after the <DT> of the link.
+fn netscape_description(link: ElementRef) -> String {
+    link.parent()
+        .and_then(|term| term.next_siblings().filter_map(ElementRef::wrap).next())
+        .filter(|element| element.value().name() == "dd")
+        .map(|element| element.text().collect::<Vec<_>>().join(" ").split_whitespace().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default()
+}
+
+pub fn parse_json_bookmarks(json_file: &str) -> Result<Vec<Bookmark>> {
+    let json = fs::read_to_string(json_file)?;
+    // An unknown state is already refused by serde, the rating is only a number there.
+    let bookmarks: Vec<Bookmark> = serde_json::from_str(&json)?;
+
+    for (index, bm) in bookmarks.iter().enumerate() {
+        if let Some(rating) = bm.rating && !(1..=5).contains(&rating) {
+            return Err(BookmanError::Parse(format!(
+                "entry {} ({}): invalid rating {}, expected a number from 1 to 5", index + 1, bm.url, rating
+            )));
+        }
     }
 
     Ok(bookmarks)
 }
 
-pub fn parse_html_text(text: &String) -> Result<String, Box<dyn Error>> {
-    let document = Html::parse_document(&text); // Parse HTML
+pub fn parse_html_text(text: &str) -> Result<String> {
+    let document = Html::parse_document(text); // Parse HTML
     let selector = Selector::parse("title").unwrap(); // Select `<title>` tag
 
     if let Some(title) = document.select(&selector).next() {
         Ok(title.text().collect::<String>().trim().to_string()) // Extract and return text
     } else {
-        Err("Title not found".into())
+        Err(BookmanError::NotFound("title not found".to_string()))
+    }
+}
+
+pub fn parse_html_description(text: &str) -> Result<String> {
+    let document = Html::parse_document(text);
+    let selector = Selector::parse("meta[name=description], meta[property=\"og:description\"]").unwrap();
+
+    for element in document.select(&selector) {
+        if let Some(content) = element.value().attr("content") {
+            let content = content.trim();
+            if !content.is_empty() {
+                return Ok(content.to_string());
+            }
+        }
     }
+
+    Err(BookmanError::NotFound("description not found".to_string()))
+}
+
+pub fn parse_html_snippet(text: &str, length: usize) -> Result<String> {
+    let document = Html::parse_document(text);
+    let selector = Selector::parse("p").unwrap();
+
+    let mut snippet = String::new();
+    for element in document.select(&selector) {
+        let paragraph = element.text().collect::<Vec<_>>().join(" ");
+        let paragraph = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+        if paragraph.is_empty() {
+            continue;
+        }
+        if !snippet.is_empty() {
+            snippet.push(' ');
+        }
+        snippet.push_str(&paragraph);
+        if snippet.chars().count() >= length {
+            break;
+        }
+    }
+
+    if snippet.is_empty() {
+        return Err(BookmanError::NotFound("no text found".to_string()));
+    }
+
+    Ok(snippet.chars().take(length).collect())
 }
@@ -0,0 +1,247 @@
+/*
+    This file is a part of bookman software.
+
+    This module contains the interactive bookmark picker and its keybindings.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use crate::config;
+use crate::entry::Bookmark;
+use crate::error::{BookmanError, Result};
+use crate::parser::Picker;
+
+use skim::prelude::{Event, SkimOptionsBuilder};
+use skim::{Skim, SkimItem, SkimItemReceiver, SkimItemSender};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Open,
+    CopyUrl,
+    CopyMarkdown,
+    Edit,
+    Delete,
+    Tag,
+    Archive,
+}
+
+// Enter runs the default action of the command, these keys run the others.
+const KEYS: &[(&str, Action)] = &[
+    ("ctrl-o", Action::Open),
+    ("ctrl-y", Action::CopyUrl),
+    ("alt-m", Action::CopyMarkdown),
+    ("ctrl-e", Action::Edit),
+    ("alt-d", Action::Delete),
+    ("alt-t", Action::Tag),
+    ("alt-a", Action::Archive),
+];
+
+const HEADER: &str = "tab: select | ctrl-o: open | ctrl-y: copy | alt-m: markdown | ctrl-e: edit | alt-d: delete | alt-t: tag | alt-a: archive";
+
+/// Runs the picker and returns the chosen action together with the selected bookmarks.
+pub fn pick(bookmarks: Vec<Bookmark>, default: Action, picker: Picker) -> Result<Option<(Action, Vec<Bookmark>)>> {
+    match picker {
+        Picker::Skim => Ok(pick_skim(bookmarks, default)),
+        menu => pick_external(&bookmarks, default, menu),
+    }
+}
+
+fn pick_skim(bookmarks: Vec<Bookmark>, default: Action) -> Option<(Action, Vec<Bookmark>)> {
+    let options = SkimOptionsBuilder::default()
+        .multi(true)
+        .preview(Some(String::new()))
+        .header(Some(HEADER.to_string()))
+        .bind(KEYS.iter().map(|(key, _)| format!("{}:accept({})", key, key)).collect::<Vec<_>>())
+        .build()
+        .unwrap();
+
+    let (tx, rx): (SkimItemSender, SkimItemReceiver) = skim::prelude::unbounded();
+    for bm in &bookmarks {
+        let _ = tx.send(Arc::new(bm.clone()));
+    }
+    drop(tx);
+
+    let output = Skim::run_with(&options, Some(rx))?;
+    if output.is_abort {
+        return None;
+    }
+
+    let action = match &output.final_event {
+        Event::EvActAccept(Some(key)) => KEYS.iter().find(|(k, _)| k == key).map_or(default, |(_, action)| *action),
+        _ => default,
+    };
+
+    let selected = selected_bookmarks(&output.selected_items);
+    if selected.is_empty() {
+        return None;
+    }
+
+    Some((action, selected))
+}
+
+// Menu programs reading lines on stdin and printing the selected ones.
+fn menu_command(picker: Picker) -> (&'static str, Vec<String>) {
+    let (program, args): (&str, &[&str]) = match picker {
+        Picker::Dmenu => ("dmenu", &["-i", "-l", "20", "-p", "bookman"]),
+        Picker::Rofi => ("rofi", &["-dmenu", "-i", "-multi-select", "-p", "bookman"]),
+        Picker::Fuzzel => ("fuzzel", &["--dmenu", "--prompt", "bookman: "]),
+        Picker::Wofi => ("wofi", &["--dmenu", "--insensitive", "--prompt", "bookman"]),
+        Picker::Fzf | Picker::Skim => ("fzf", &["--multi", "--header", HEADER]),
+    };
+    let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+
+    // fzf prints the key that ended the selection on the first line.
+    if program == "fzf" {
+        args.push(format!("--expect={}", KEYS.iter().map(|(key, _)| *key).collect::<Vec<_>>().join(",")));
+    }
+
+    (program, args)
+}
+
+// Menus only hand back text, so every line starts with the bookmark ID.
+fn menu_line(bm: &Bookmark) -> String {
+    if config::DISPLAY_TEMPLATE.starts_with("{id}") {
+        bm.format(config::DISPLAY_TEMPLATE)
+    } else {
+        format!("{} | {}", bm.id, bm.format(config::DISPLAY_TEMPLATE))
+    }
+}
+
+fn pick_external(bookmarks: &[Bookmark], default: Action, picker: Picker) -> Result<Option<(Action, Vec<Bookmark>)>> {
+    let (program, args) = menu_command(picker);
+    let mut child = Command::new(program)
+        .args(&args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| BookmanError::External(format!("failed to run {}: {}", program, err)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let lines: Vec<String> = bookmarks.iter().map(menu_line).collect();
+        // The menu may quit before reading everything.
+        let _ = stdin.write_all((lines.join("\n") + "\n").as_bytes());
+    }
+
+    let output = child.wait_with_output()?;
+    // Menus exit with an error when nothing is selected.
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(parse_selection(&String::from_utf8_lossy(&output.stdout), bookmarks, default, picker == Picker::Fzf))
+}
+
+// Maps the lines printed by a menu back to the bookmarks through their leading IDs.
+fn parse_selection(output: &str, bookmarks: &[Bookmark], default: Action, with_key: bool) -> Option<(Action, Vec<Bookmark>)> {
+    let mut lines = output.lines();
+
+    let action = if with_key {
+        let key = lines.next().unwrap_or_default();
+        KEYS.iter().find(|(k, _)| *k == key).map_or(default, |(_, action)| *action)
+    } else {
+        default
+    };
+
+    let selected: Vec<Bookmark> = lines
+        .filter_map(|line| {
+            let id: String = line.trim_start().chars().take_while(|c| c.is_ascii_digit()).collect();
+            id.parse::<i32>().ok()
+        })
+        .filter_map(|id| bookmarks.iter().find(|bm| bm.id == id).cloned())
+        .collect();
+
+    if selected.is_empty() {
+        None
+    } else {
+        Some((action, selected))
+    }
+}
+
+// Skim hands back the very items it was fed, so recover the bookmarks (and their IDs)
+// by downcasting instead of matching the displayed text.
+fn selected_bookmarks(items: &[Arc<dyn SkimItem>]) -> Vec<Bookmark> {
+    items.iter()
+        .filter_map(|item| item.as_any().downcast_ref::<Bookmark>())
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(id: i32, name: &str, url: &str) -> Arc<dyn SkimItem> {
+        Arc::new(Bookmark { id, name: name.to_string(), url: url.to_string(), ..Default::default() })
+    }
+
+    fn ids(items: &[Arc<dyn SkimItem>]) -> Vec<i32> {
+        selected_bookmarks(items).iter().map(|bm| bm.id).collect()
+    }
+
+    #[test]
+    fn overlapping_urls_map_to_selected_bookmark() {
+        let short = bookmark(1, "Example", "https://example.com");
+        let long = bookmark(2, "Example docs", "https://example.com/docs");
+
+        assert_eq!(ids(std::slice::from_ref(&long)), vec![2]);
+        assert_eq!(ids(std::slice::from_ref(&short)), vec![1]);
+        assert_eq!(ids(&[long, short]), vec![2, 1]);
+    }
+
+    #[test]
+    fn duplicate_names_map_to_selected_bookmark() {
+        let first = bookmark(3, "Rust", "https://rust-lang.org");
+        let second = bookmark(4, "Rust", "https://doc.rust-lang.org");
+
+        assert_eq!(ids(&[second]), vec![4]);
+        assert_eq!(ids(&[first]), vec![3]);
+    }
+
+    #[test]
+    fn duplicate_urls_map_to_selected_bookmark() {
+        let first = bookmark(5, "Docs", "https://example.com/docs");
+        let second = bookmark(6, "Docs", "https://example.com/docs");
+
+        assert_eq!(ids(&[second, first]), vec![6, 5]);
+    }
+
+    #[test]
+    fn menu_output_maps_to_bookmarks() {
+        let bookmarks: Vec<Bookmark> = (1..=12)
+            .map(|id| Bookmark { id, name: format!("Bookmark {}", id), ..Default::default() })
+            .collect();
+        let ids = |selection: Option<(Action, Vec<Bookmark>)>| {
+            selection.map(|(action, selected)| (action, selected.iter().map(|bm| bm.id).collect::<Vec<_>>()))
+        };
+
+        assert_eq!(ids(parse_selection("12 | Bookmark 12\n1 | Bookmark 1\n", &bookmarks, Action::Open, false)), Some((Action::Open, vec![12, 1])));
+        assert_eq!(ids(parse_selection("alt-d\n3 | Bookmark 3\n", &bookmarks, Action::Open, true)), Some((Action::Delete, vec![3])));
+        assert_eq!(ids(parse_selection("\n3 | Bookmark 3\n", &bookmarks, Action::CopyUrl, true)), Some((Action::CopyUrl, vec![3])));
+        assert_eq!(ids(parse_selection("typed text\n99 | gone\n", &bookmarks, Action::Open, false)), None);
+    }
+
+    #[test]
+    fn foreign_items_are_ignored() {
+        let line: Arc<dyn SkimItem> = Arc::new("1 | Example | https://example.com".to_string());
+
+        assert!(ids(&[line]).is_empty());
+    }
+}
@@ -0,0 +1,392 @@
+/*
+    This file is a part of bookman software.
+
+    This module contains the parser of the search query language, e.g.
+        tag:rust domain:github.com added:>2025-01 -tag:archived "async runtime"
+    and its translation into SQL.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use crate::entry::{Bookmark, State};
+use crate::error::{BookmanError, Result};
+
+use chrono::{Local, NaiveDate, TimeZone};
+use rusqlite::types::Value;
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Before,
+    BeforeOrDuring,
+    During,
+    AfterOrDuring,
+    After,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Tag(String),
+    Domain(String),
+    Folder(String),
+    State(State),
+    Starred,
+    Rating(Comparison, u8),
+    /// A period of time given as [start, end) unix timestamps.
+    Added(Comparison, i64, i64),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub term: Term,
+    pub negated: bool,
+}
+
+/// A query is a list of filters that all have to match.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub filters: Vec<Filter>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query> {
+        let mut filters = Vec::new();
+
+        for token in tokenize(input)? {
+            let (negated, token) = match token.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest.to_string()),
+                _ => (false, token),
+            };
+
+            let term = match token.split_once(':') {
+                Some(("tag", value)) => Term::Tag(value.to_lowercase()),
+                Some(("domain", value)) => Term::Domain(value.to_lowercase()),
+                Some(("folder", value)) => Term::Folder(value.to_string()),
+                Some(("is", "starred")) => Term::Starred,
+                Some(("is", value)) => Term::State(State::parse(value)
+                    .ok_or_else(|| BookmanError::Parse(format!("unknown state '{}', expected unread, read, archived or starred", value)))?),
+                Some(("rating", value)) => parse_rating(value)?,
+                Some(("added", value)) => parse_added(value)?,
+                _ => Term::Text(token),
+            };
+
+            filters.push(Filter { term, negated });
+        }
+
+        Ok(Query { filters })
+    }
+
+    /// Evaluates the query against a bookmark, the same way its SQL translation does.
+    pub fn matches(&self, bookmark: &Bookmark) -> bool {
+        self.filters.iter().all(|filter| filter.term.matches(bookmark) != filter.negated)
+    }
+
+    /// Translates the query into a SQL condition over the `bookmarks b` table and its parameters.
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+
+        for filter in &self.filters {
+            let condition = match &filter.term {
+                Term::Tag(tag) => {
+                    params.push(Value::Text(tag.clone()));
+                    "EXISTS (SELECT 1 FROM tags q WHERE q.bookmark_id = b.id AND q.name = ?)".to_string()
+                }
+                Term::Domain(domain) => {
+                    params.push(Value::Text(domain.clone()));
+                    params.push(Value::Text(format!("%.{}", escape_like(domain))));
+                    "(url_host(b.url) = ? OR url_host(b.url) LIKE ? ESCAPE '\\')".to_string()
+                }
+                Term::Folder(folder) => {
+                    params.push(Value::Text(folder.clone()));
+                    "COALESCE(b.folder, '') = ? COLLATE NOCASE".to_string()
+                }
+                Term::State(state) => {
+                    params.push(Value::Text(state.as_str().to_string()));
+                    "b.state = ?".to_string()
+                }
+                Term::Starred => "b.starred = 1".to_string(),
+                Term::Rating(comparison, rating) => {
+                    let operator = match comparison {
+                        Comparison::Before => "<",
+                        Comparison::BeforeOrDuring => "<=",
+                        Comparison::During => "=",
+                        Comparison::AfterOrDuring => ">=",
+                        Comparison::After => ">",
+                    };
+                    params.push(Value::Integer(*rating as i64));
+                    format!("COALESCE(b.rating {} ?, 0)", operator)
+                }
+                Term::Added(comparison, start, end) => {
+                    let (condition, bounds) = match comparison {
+                        Comparison::Before => ("b.added < ?", vec![*start]),
+                        Comparison::BeforeOrDuring => ("b.added < ?", vec![*end]),
+                        Comparison::During => ("(b.added >= ? AND b.added < ?)", vec![*start, *end]),
+                        Comparison::AfterOrDuring => ("b.added >= ?", vec![*start]),
+                        Comparison::After => ("b.added >= ?", vec![*end]),
+                    };
+                    params.extend(bounds.into_iter().map(Value::Integer));
+                    format!("COALESCE({}, 0)", condition)
+                }
+                Term::Text(text) => {
+                    let pattern = format!("%{}%", escape_like(text));
+                    for _ in 0..3 {
+                        params.push(Value::Text(pattern.clone()));
+                    }
+                    "(COALESCE(b.name, '') LIKE ? ESCAPE '\\' OR COALESCE(b.url, '') LIKE ? ESCAPE '\\' \
+                     OR COALESCE(b.description, '') LIKE ? ESCAPE '\\')".to_string()
+                }
+            };
+
+            if filter.negated {
+                conditions.push(format!("NOT {}", condition));
+            } else {
+                conditions.push(condition);
+            }
+        }
+
+        if conditions.is_empty() {
+            return ("1".to_string(), params);
+        }
+
+        (conditions.join(" AND "), params)
+    }
+}
+
+impl Term {
+    fn matches(&self, bookmark: &Bookmark) -> bool {
+        match self {
+            Term::Tag(tag) => bookmark.tags.contains(tag),
+            Term::Domain(domain) => Url::parse(&bookmark.url).ok()
+                .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+                .is_some_and(|host| host == *domain || host.ends_with(&format!(".{}", domain))),
+            Term::Folder(folder) => bookmark.folder.eq_ignore_ascii_case(folder),
+            Term::State(state) => bookmark.state == *state,
+            Term::Starred => bookmark.starred,
+            Term::Rating(comparison, rating) => bookmark.rating.is_some_and(|r| match comparison {
+                Comparison::Before => r < *rating,
+                Comparison::BeforeOrDuring => r <= *rating,
+                Comparison::During => r == *rating,
+                Comparison::AfterOrDuring => r >= *rating,
+                Comparison::After => r > *rating,
+            }),
+            Term::Added(comparison, start, end) => bookmark.added.is_some_and(|added| match comparison {
+                Comparison::Before => added < *start,
+                Comparison::BeforeOrDuring => added < *end,
+                Comparison::During => added >= *start && added < *end,
+                Comparison::AfterOrDuring => added >= *start,
+                Comparison::After => added >= *end,
+            }),
+            // LIKE ignores the case of ASCII letters only.
+            Term::Text(text) => {
+                let text = text.to_ascii_lowercase();
+                [&bookmark.name, &bookmark.url, &bookmark.description]
+                    .iter()
+                    .any(|field| field.to_ascii_lowercase().contains(&text))
+            }
+        }
+    }
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+// Splits on whitespace, keeping "quoted phrases" (also as values, e.g. tag:"to read") together.
+fn tokenize(input: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => {
+                current.push(c);
+            }
+        }
+    }
+
+    if quoted {
+        return Err(BookmanError::Parse("unterminated quote in query".to_string()));
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_comparison(value: &str) -> (Comparison, &str) {
+    if let Some(rest) = value.strip_prefix(">=") {
+        (Comparison::AfterOrDuring, rest)
+    } else if let Some(rest) = value.strip_prefix("<=") {
+        (Comparison::BeforeOrDuring, rest)
+    } else if let Some(rest) = value.strip_prefix('>') {
+        (Comparison::After, rest)
+    } else if let Some(rest) = value.strip_prefix('<') {
+        (Comparison::Before, rest)
+    } else {
+        (Comparison::During, value.strip_prefix('=').unwrap_or(value))
+    }
+}
+
+fn parse_rating(value: &str) -> Result<Term> {
+    let (comparison, rating) = parse_comparison(value);
+
+    match rating.parse::<u8>() {
+        Ok(rating) if (1..=5).contains(&rating) => Ok(Term::Rating(comparison, rating)),
+        _ => Err(BookmanError::Parse(format!("invalid rating '{}', expected a number from 1 to 5", rating))),
+    }
+}
+
+fn parse_added(value: &str) -> Result<Term> {
+    let (comparison, date) = parse_comparison(value);
+
+    let (start, end) = parse_period(date)
+        .ok_or_else(|| BookmanError::Parse(format!("invalid date '{}', expected YYYY, YYYY-MM or YYYY-MM-DD", date)))?;
+
+    Ok(Term::Added(comparison, start, end))
+}
+
+// "2025" covers the whole year, "2025-01" the month and "2025-01-15" the day.
+fn parse_period(date: &str) -> Option<(i64, i64)> {
+    let parts: Vec<&str> = date.split('-').collect();
+    let numbers: Vec<u32> = parts.iter().map(|p| p.parse().ok()).collect::<Option<_>>()?;
+
+    let (start, end) = match numbers.as_slice() {
+        [year] => {
+            let year = *year as i32;
+            (NaiveDate::from_ymd_opt(year, 1, 1)?, NaiveDate::from_ymd_opt(year + 1, 1, 1)?)
+        }
+        [year, month] => {
+            let year = *year as i32;
+            let start = NaiveDate::from_ymd_opt(year, *month, 1)?;
+            let end = if *month == 12 {
+                NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+            } else {
+                NaiveDate::from_ymd_opt(year, month + 1, 1)?
+            };
+            (start, end)
+        }
+        [year, month, day] => {
+            let start = NaiveDate::from_ymd_opt(*year as i32, *month, *day)?;
+            (start, start.succ_opt()?)
+        }
+        _ => return None,
+    };
+
+    let timestamp = |date: NaiveDate| {
+        Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest().map(|dt| dt.timestamp())
+    };
+
+    Some((timestamp(start)?, timestamp(end)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fields_negation_and_phrases() {
+        let query = Query::parse(r#"tag:Rust domain:github.com -tag:archived "async runtime" folder:"Dev Tools""#).unwrap();
+
+        assert_eq!(query.filters, vec![
+            Filter { term: Term::Tag("rust".to_string()), negated: false },
+            Filter { term: Term::Domain("github.com".to_string()), negated: false },
+            Filter { term: Term::Tag("archived".to_string()), negated: true },
+            Filter { term: Term::Text("async runtime".to_string()), negated: false },
+            Filter { term: Term::Folder("Dev Tools".to_string()), negated: false },
+        ]);
+    }
+
+    #[test]
+    fn parses_date_periods() {
+        let query = Query::parse("added:>2025-01").unwrap();
+        let (start, end) = parse_period("2025-01").unwrap();
+
+        assert_eq!(query.filters[0].term, Term::Added(Comparison::After, start, end));
+        assert_eq!(end - start, 31 * 24 * 3600);
+        assert!(Query::parse("added:>2025-13").is_err());
+        assert!(Query::parse("added:yesterday").is_err());
+    }
+
+    #[test]
+    fn parses_states() {
+        let query = Query::parse("is:unread -is:archived").unwrap();
+
+        assert_eq!(query.filters[0].term, Term::State(State::Unread));
+        assert!(query.filters[1].negated);
+        assert!(Query::parse("is:someday").is_err());
+    }
+
+    #[test]
+    fn parses_favorites_and_ratings() {
+        let query = Query::parse("is:starred rating:>=4").unwrap();
+
+        assert_eq!(query.filters[0].term, Term::Starred);
+        assert_eq!(query.filters[1].term, Term::Rating(Comparison::AfterOrDuring, 4));
+        assert!(Query::parse("rating:6").is_err());
+    }
+
+    #[test]
+    fn unknown_fields_are_text() {
+        let query = Query::parse("https://example.com").unwrap();
+
+        assert_eq!(query.filters[0].term, Term::Text("https://example.com".to_string()));
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        assert!(Query::parse(r#"tag:rust "async"#).is_err());
+    }
+
+    #[test]
+    fn matches_bookmarks() {
+        let bookmark = Bookmark {
+            name: "Tokio".to_string(),
+            url: "https://docs.rs/tokio".to_string(),
+            tags: vec!["async".to_string(), "rust".to_string()],
+            rating: Some(4),
+            ..Default::default()
+        };
+
+        assert!(Query::parse("tag:rust domain:rs TOKIO rating:>=4").unwrap().matches(&bookmark));
+        assert!(Query::parse("-tag:done -is:starred").unwrap().matches(&bookmark));
+        assert!(!Query::parse("-tag:async").unwrap().matches(&bookmark));
+        assert!(!Query::parse("domain:docs").unwrap().matches(&bookmark));
+    }
+
+    #[test]
+    fn compiles_to_parameterized_sql() {
+        let (sql, params) = Query::parse("-tag:done 100%").unwrap().to_sql();
+
+        assert!(sql.starts_with("NOT EXISTS"));
+        assert!(!sql.contains("done"));
+        assert_eq!(params[0], Value::Text("done".to_string()));
+        assert_eq!(params[1], Value::Text("%100\\%%".to_string()));
+    }
+}
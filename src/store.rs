@@ -0,0 +1,232 @@
+/*
+    This file is a part of bookman software.
+
+    It contains the BookmarkStore trait used by the commands and Store, its
+    implementation over the encrypted SQLite database.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use crate::config;
+use crate::db_driver;
+use crate::entry::{Bookmark, Change, State};
+use crate::error::{BookmanError, Result};
+use crate::query::Query;
+use crate::utils;
+
+use rusqlite::Connection;
+use std::fs;
+use std::path::Path;
+
+/// Where bookmarks, their history and saved collections are kept.
+pub trait BookmarkStore {
+    /// Saves a new bookmark and returns its ID.
+    fn add(&self, bookmark: &Bookmark) -> Result<i32>;
+    fn get(&self, id: i32) -> Result<Bookmark>;
+    fn all(&self) -> Result<Vec<Bookmark>>;
+    fn filter(&self, query: &Query) -> Result<Vec<Bookmark>>;
+    fn update(&self, bookmark: &Bookmark) -> Result<()>;
+    fn set_tags(&self, id: i32, tags: &[String]) -> Result<()>;
+    fn set_state(&self, id: i32, state: State) -> Result<()>;
+    fn record_visit(&self, id: i32) -> Result<()>;
+    fn record_event(&self, id: i32, kind: &str) -> Result<()>;
+    /// Moves the bookmark to the trash.
+    fn remove(&self, id: i32) -> Result<()>;
+    fn trash(&self) -> Result<Vec<Bookmark>>;
+    fn restore(&self, id: i32) -> Result<()>;
+    /// Deletes all bookmarks in the trash for good and returns how many there were.
+    fn empty_trash(&self) -> Result<usize>;
+    fn history(&self, id: Option<i32>, limit: usize) -> Result<Vec<Change>>;
+    /// Reverts the last `count` commands and returns the number of reverted changes.
+    fn undo(&self, count: usize) -> Result<usize>;
+    /// Saves a query as a named collection, the query has to be valid.
+    fn add_collection(&self, name: &str, query: &str) -> Result<()>;
+    /// Returns the query of a collection.
+    fn collection(&self, name: &str) -> Result<String>;
+    fn collections(&self) -> Result<Vec<(String, String)>>;
+    fn remove_collection(&self, name: &str) -> Result<()>;
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Opens (or creates) the database at `path`, encrypted with `key`.
+    pub fn open(path: &Path, key: &str) -> Result<Store> {
+        if let Some(dir) = path.parent() && !dir.exists() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "key", key)?;
+        // The key is only checked once the database is read for the first time.
+        conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))?;
+
+        Store::init(conn)
+    }
+
+    /// Opens a fresh database that only lives as long as the store.
+    pub fn open_in_memory() -> Result<Store> {
+        Store::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Store> {
+        db_driver::new_table(&conn)?;
+        db_driver::register_functions(&conn)?;
+        db_driver::purge_trash(&conn, config::TRASH_RETENTION_DAYS * 24 * 3600)?;
+
+        Ok(Store { conn })
+    }
+}
+
+impl BookmarkStore for Store {
+    fn add(&self, bookmark: &Bookmark) -> Result<i32> {
+        let bookmark = Bookmark { tags: utils::clean_tags(&bookmark.tags), ..bookmark.clone() };
+
+        db_driver::insert_entry(&self.conn, &bookmark)
+    }
+
+    fn get(&self, id: i32) -> Result<Bookmark> {
+        db_driver::get_entry(&self.conn, id)
+    }
+
+    fn all(&self) -> Result<Vec<Bookmark>> {
+        db_driver::get_iterator(&self.conn)
+    }
+
+    fn filter(&self, query: &Query) -> Result<Vec<Bookmark>> {
+        db_driver::get_filtered(&self.conn, query)
+    }
+
+    fn update(&self, bookmark: &Bookmark) -> Result<()> {
+        db_driver::update_entry(&self.conn, bookmark)
+    }
+
+    fn set_tags(&self, id: i32, tags: &[String]) -> Result<()> {
+        db_driver::set_tags(&self.conn, id, &utils::clean_tags(tags))
+    }
+
+    fn set_state(&self, id: i32, state: State) -> Result<()> {
+        db_driver::set_state(&self.conn, id, state)
+    }
+
+    fn record_visit(&self, id: i32) -> Result<()> {
+        db_driver::record_visit(&self.conn, id)
+    }
+
+    fn record_event(&self, id: i32, kind: &str) -> Result<()> {
+        db_driver::record_event(&self.conn, id, kind)
+    }
+
+    fn remove(&self, id: i32) -> Result<()> {
+        match db_driver::remove_entry(&self.conn, id)? {
+            0 => Err(BookmanError::NotFound(format!("bookmark {} not found", id))),
+            _ => Ok(()),
+        }
+    }
+
+    fn trash(&self) -> Result<Vec<Bookmark>> {
+        db_driver::get_trash(&self.conn)
+    }
+
+    fn restore(&self, id: i32) -> Result<()> {
+        match db_driver::restore_entry(&self.conn, id)? {
+            0 => Err(BookmanError::NotFound(format!("bookmark {} is not in the trash", id))),
+            _ => Ok(()),
+        }
+    }
+
+    fn empty_trash(&self) -> Result<usize> {
+        db_driver::empty_trash(&self.conn)
+    }
+
+    fn history(&self, id: Option<i32>, limit: usize) -> Result<Vec<Change>> {
+        db_driver::get_history(&self.conn, id, limit)
+    }
+
+    fn undo(&self, count: usize) -> Result<usize> {
+        match db_driver::undo(&self.conn, count)? {
+            0 => Err(BookmanError::NotFound("there is nothing to undo".to_string())),
+            reverted => Ok(reverted),
+        }
+    }
+
+    fn add_collection(&self, name: &str, query: &str) -> Result<()> {
+        Query::parse(query)?;
+
+        if db_driver::get_collection(&self.conn, name)?.is_some() {
+            return Err(BookmanError::Duplicate(format!("collection '{}' already exists", name)));
+        }
+
+        db_driver::insert_collection(&self.conn, name, query)
+    }
+
+    fn collection(&self, name: &str) -> Result<String> {
+        db_driver::get_collection(&self.conn, name)?
+            .ok_or_else(|| BookmanError::NotFound(format!("collection '{}' does not exist", name)))
+    }
+
+    fn collections(&self) -> Result<Vec<(String, String)>> {
+        db_driver::get_collections(&self.conn)
+    }
+
+    fn remove_collection(&self, name: &str) -> Result<()> {
+        match db_driver::remove_collection(&self.conn, name)? {
+            0 => Err(BookmanError::NotFound(format!("collection '{}' does not exist", name))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn wrong_key_is_reported() {
+        let path = env::temp_dir().join(format!("bookman-test-{}.db", process::id()));
+        let _ = fs::remove_file(&path);
+
+        Store::open(&path, "first key").unwrap().add(&Bookmark { name: "Rust".to_string(), ..Default::default() }).unwrap();
+        let reopened = Store::open(&path, "second key");
+        let _ = fs::remove_file(&path);
+
+        assert!(matches!(reopened, Err(BookmanError::WrongKey)));
+    }
+
+    #[test]
+    fn missing_entries_are_not_found() {
+        let store = Store::open_in_memory().unwrap();
+
+        assert!(matches!(store.get(1), Err(BookmanError::NotFound(_))));
+        assert!(matches!(store.remove(1), Err(BookmanError::NotFound(_))));
+        assert!(matches!(store.collection("work"), Err(BookmanError::NotFound(_))));
+    }
+
+    #[test]
+    fn duplicate_collections_are_rejected() {
+        let store = Store::open_in_memory().unwrap();
+        store.add_collection("work", "tag:work").unwrap();
+
+        assert!(matches!(store.add_collection("work", "tag:job"), Err(BookmanError::Duplicate(_))));
+        assert!(matches!(store.add_collection("job", "rating:9"), Err(BookmanError::Parse(_))));
+    }
+}
@@ -0,0 +1,493 @@
+/*
+    This file is a part of bookman software.
+
+    It contains the full-screen interface started by `bookman tui`: a sidebar
+    with tags and folders, the list of bookmarks filtered as you type and the
+    details of the selected one.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use crate::config;
+use crate::entry::{Bookmark, State};
+use crate::error::Result;
+use crate::manager;
+use crate::parser::{AddArgs, LinkFormat, Sort};
+use crate::query::Query;
+use crate::store::BookmarkStore;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+const HELP: &str = "q: quit | tab: switch pane | /: filter | a: add | e: edit | d: delete | t: tag | o: open | y: copy";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Scope {
+    All,
+    Tag(String),
+    Folder(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Focus {
+    Sidebar,
+    List,
+}
+
+// What the bottom line is asking for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Prompt {
+    Filter,
+    Add,
+    Tag,
+    Delete,
+}
+
+#[derive(Debug, PartialEq)]
+enum Flow {
+    Continue,
+    // Something else may have written to the terminal.
+    Redraw,
+    Edit,
+    Quit,
+}
+
+struct App<'a> {
+    store: &'a dyn BookmarkStore,
+    bookmarks: Vec<Bookmark>,
+    scopes: Vec<Scope>,
+    sidebar: ListState,
+    list: ListState,
+    focus: Focus,
+    filter: String,
+    prompt: Option<Prompt>,
+    input: String,
+    status: String,
+}
+
+impl<'a> App<'a> {
+    fn new(store: &'a dyn BookmarkStore) -> Result<App<'a>> {
+        let mut app = App {
+            store,
+            bookmarks: Vec::new(),
+            scopes: Vec::new(),
+            sidebar: ListState::default().with_selected(Some(0)),
+            list: ListState::default().with_selected(Some(0)),
+            focus: Focus::List,
+            filter: String::new(),
+            prompt: None,
+            input: String::new(),
+            status: String::new(),
+        };
+        app.reload()?;
+
+        Ok(app)
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        self.bookmarks = manager::filtered(self.store, "", Sort::Frecency)?;
+
+        let mut tags: Vec<&String> = self.bookmarks.iter().flat_map(|bm| &bm.tags).collect();
+        tags.sort();
+        tags.dedup();
+        let mut folders: Vec<&String> = self.bookmarks.iter().map(|bm| &bm.folder).filter(|f| !f.is_empty()).collect();
+        folders.sort();
+        folders.dedup();
+
+        let scope = self.scope();
+        self.scopes = std::iter::once(Scope::All)
+            .chain(tags.into_iter().map(|tag| Scope::Tag(tag.clone())))
+            .chain(folders.into_iter().map(|folder| Scope::Folder(folder.clone())))
+            .collect();
+        // Stay in the same tag or folder unless it's gone.
+        self.sidebar.select(Some(self.scopes.iter().position(|s| *s == scope).unwrap_or(0)));
+        self.clamp();
+
+        Ok(())
+    }
+
+    fn scope(&self) -> Scope {
+        self.sidebar.selected().and_then(|i| self.scopes.get(i)).cloned().unwrap_or(Scope::All)
+    }
+
+    fn visible(&self) -> Vec<&Bookmark> {
+        let scope = self.scope();
+        // Incomplete queries (e.g. "tag:") don't hide anything while typing.
+        let query = Query::parse(&self.filter).ok();
+
+        self.bookmarks.iter()
+            .filter(|bm| match &scope {
+                Scope::All => true,
+                Scope::Tag(tag) => bm.tags.contains(tag),
+                Scope::Folder(folder) => bm.folder == *folder || bm.folder.starts_with(&format!("{}/", folder)),
+            })
+            .filter(|bm| query.as_ref().is_none_or(|query| query.matches(bm)))
+            .collect()
+    }
+
+    fn selected(&self) -> Option<Bookmark> {
+        self.visible().get(self.list.selected()?).map(|bm| (*bm).clone())
+    }
+
+    fn clamp(&mut self) {
+        let count = self.visible().len();
+        let selected = self.list.selected().unwrap_or(0).min(count.saturating_sub(1));
+        self.list.select(Some(selected));
+    }
+
+    fn move_by(&mut self, offset: isize) {
+        let (state, count) = match self.focus {
+            Focus::Sidebar => (&mut self.sidebar, self.scopes.len()),
+            Focus::List => {
+                let count = self.visible().len();
+                (&mut self.list, count)
+            }
+        };
+        let selected = state.selected().unwrap_or(0).saturating_add_signed(offset);
+        state.select(Some(selected.min(count.saturating_sub(1))));
+
+        if self.focus == Focus::Sidebar {
+            self.list.select(Some(0));
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Result<Flow> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Ok(Flow::Quit);
+        }
+
+        match self.prompt {
+            Some(prompt) => self.handle_prompt(prompt, key.code),
+            None => self.handle_command(key.code),
+        }
+    }
+
+    fn handle_command(&mut self, code: KeyCode) -> Result<Flow> {
+        self.status.clear();
+
+        match code {
+            KeyCode::Char('q') => return Ok(Flow::Quit),
+            KeyCode::Esc if self.filter.is_empty() => return Ok(Flow::Quit),
+            KeyCode::Esc => {
+                self.filter.clear();
+                self.clamp();
+            }
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Focus::Sidebar => Focus::List,
+                    Focus::List => Focus::Sidebar,
+                };
+            }
+            KeyCode::Char('h') | KeyCode::Left => self.focus = Focus::Sidebar,
+            KeyCode::Char('l') | KeyCode::Right => self.focus = Focus::List,
+            KeyCode::Char('j') | KeyCode::Down => self.move_by(1),
+            KeyCode::Char('k') | KeyCode::Up => self.move_by(-1),
+            KeyCode::PageDown => self.move_by(10),
+            KeyCode::PageUp => self.move_by(-10),
+            KeyCode::Char('g') | KeyCode::Home => self.move_by(isize::MIN),
+            KeyCode::Char('G') | KeyCode::End => self.move_by(isize::MAX),
+            KeyCode::Char('/') => {
+                self.focus = Focus::List;
+                self.prompt = Some(Prompt::Filter);
+            }
+            KeyCode::Char('a') => {
+                self.prompt = Some(Prompt::Add);
+                self.input.clear();
+            }
+            KeyCode::Enter if self.focus == Focus::Sidebar => self.focus = Focus::List,
+            KeyCode::Enter | KeyCode::Char('o') => {
+                if let Some(bookmark) = self.selected() {
+                    manager::open_bookmark(self.store, &bookmark)?;
+                    self.status = format!("Opened {}", bookmark.url);
+                    self.reload()?;
+                    return Ok(Flow::Redraw);
+                }
+            }
+            KeyCode::Char('y') => {
+                if let Some(bookmark) = self.selected() {
+                    manager::copy(self.store, LinkFormat::Plain, std::slice::from_ref(&bookmark), false)?;
+                    self.status = format!("Copied {}", bookmark.url);
+                    return Ok(Flow::Redraw);
+                }
+            }
+            KeyCode::Char('e') if self.selected().is_some() => return Ok(Flow::Edit),
+            KeyCode::Char('d') if self.selected().is_some() => self.prompt = Some(Prompt::Delete),
+            KeyCode::Char('t') if self.selected().is_some() => {
+                self.prompt = Some(Prompt::Tag);
+                self.input.clear();
+            }
+            _ => {}
+        }
+
+        Ok(Flow::Continue)
+    }
+
+    fn handle_prompt(&mut self, prompt: Prompt, code: KeyCode) -> Result<Flow> {
+        if prompt == Prompt::Delete {
+            self.prompt = None;
+            if let (KeyCode::Char('y'), Some(bookmark)) = (code, self.selected()) {
+                self.store.remove(bookmark.id)?;
+                self.status = format!("Moved {} to the trash", bookmark.name);
+                self.reload()?;
+            }
+            return Ok(Flow::Continue);
+        }
+
+        // The filter is applied while it's typed.
+        let text = match prompt {
+            Prompt::Filter => &mut self.filter,
+            _ => &mut self.input,
+        };
+
+        match code {
+            KeyCode::Char(c) => text.push(c),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Esc => {
+                text.clear();
+                self.prompt = None;
+            }
+            KeyCode::Enter => {
+                self.prompt = None;
+                return self.submit(prompt);
+            }
+            _ => {}
+        }
+
+        if prompt == Prompt::Filter {
+            self.list.select(Some(0));
+        }
+
+        Ok(Flow::Continue)
+    }
+
+    fn submit(&mut self, prompt: Prompt) -> Result<Flow> {
+        match prompt {
+            Prompt::Add if !self.input.trim().is_empty() => {
+                // Errors like an invalid URL are shown instead of leaving the interface.
+                match manager::add_url(self.store, &self.input, &AddArgs::default(), State::Read) {
+                    Ok(id) => {
+                        self.status = format!("Bookmark {} is saved", id);
+                        self.filter.clear();
+                        self.reload()?;
+                        if let Some(position) = self.visible().iter().position(|bm| bm.id == id) {
+                            self.list.select(Some(position));
+                        }
+                    }
+                    Err(err) => self.status = err.to_string(),
+                }
+            }
+            Prompt::Tag => {
+                if let Some(bookmark) = self.selected() {
+                    let (added, removed) = manager::tag_changes(&self.input);
+                    manager::retag(self.store, &[bookmark], &added, &removed)?;
+                    self.status = "Tags are updated".to_string();
+                    self.reload()?;
+                    return Ok(Flow::Redraw);
+                }
+            }
+            _ => self.clamp(),
+        }
+
+        Ok(Flow::Continue)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [side, list, detail] = Layout::horizontal([
+            Constraint::Percentage(20),
+            Constraint::Percentage(45),
+            Constraint::Percentage(35),
+        ]).areas(main);
+
+        let border = |focus: Focus| if self.focus == focus { Style::default().fg(Color::Cyan) } else { Style::default() };
+        let highlight = Style::default().add_modifier(Modifier::REVERSED);
+
+        let scopes: Vec<ListItem> = self.scopes.iter()
+            .map(|scope| ListItem::new(match scope {
+                Scope::All => format!("All ({})", self.bookmarks.len()),
+                Scope::Tag(tag) => format!("#{}", tag),
+                Scope::Folder(folder) => format!("/{}", folder),
+            }))
+            .collect();
+        let sidebar = List::new(scopes)
+            .block(Block::bordered().title("Tags & folders").border_style(border(Focus::Sidebar)))
+            .highlight_style(highlight);
+        frame.render_stateful_widget(sidebar, side, &mut self.sidebar);
+
+        let visible = self.visible();
+        let details = self.list.selected().and_then(|i| visible.get(i)).map(|bm| bm.details()).unwrap_or_default();
+        let title = format!("Bookmarks ({})", visible.len());
+        let items: Vec<ListItem> = visible.iter().map(|bm| ListItem::new(bm.format(config::DISPLAY_TEMPLATE))).collect();
+        let bookmarks = List::new(items)
+            .block(Block::bordered().title(title).border_style(border(Focus::List)))
+            .highlight_style(highlight);
+        frame.render_stateful_widget(bookmarks, list, &mut self.list);
+
+        let details = Paragraph::new(details)
+            .block(Block::bordered().title("Details"))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(details, detail);
+
+        let line = match self.prompt {
+            Some(Prompt::Filter) => format!("/{}", self.filter),
+            Some(Prompt::Add) => format!("URL: {}", self.input),
+            Some(Prompt::Tag) => format!("Tags to add (prefix with '-' to remove): {}", self.input),
+            Some(Prompt::Delete) => "Move to the trash? [y/N]".to_string(),
+            None if !self.status.is_empty() => self.status.clone(),
+            None if !self.filter.is_empty() => format!("filter: {} | esc: clear | {}", self.filter, HELP),
+            None => HELP.to_string(),
+        };
+        frame.render_widget(Paragraph::new(line), status);
+    }
+}
+
+/// Runs the interface until it's closed.
+pub fn run(store: &dyn BookmarkStore) -> Result<()> {
+    let mut app = App::new(store)?;
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        // A failed action, e.g. a browser that doesn't start, is shown instead of leaving.
+        let flow = app.handle_key(key).unwrap_or_else(|err| {
+            app.status = err.to_string();
+            Flow::Redraw
+        });
+
+        match flow {
+            Flow::Continue => {}
+            Flow::Redraw => terminal.clear()?,
+            Flow::Edit => {
+                let Some(bookmark) = app.selected() else {
+                    continue;
+                };
+
+                // The editor needs the terminal back while it runs.
+                ratatui::restore();
+                let edited = manager::edit_in_editor(app.store, &[bookmark], false);
+                *terminal = ratatui::try_init()?;
+                terminal.clear()?;
+
+                app.status = match edited {
+                    Ok(()) => "Bookmark is updated".to_string(),
+                    Err(err) => err.to_string(),
+                };
+                app.reload()?;
+            }
+            Flow::Quit => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory_store::MemoryStore;
+
+    fn key(app: &mut App, code: KeyCode) -> Flow {
+        app.handle_key(KeyEvent::from(code)).unwrap()
+    }
+
+    fn names(app: &App) -> Vec<String> {
+        app.visible().iter().map(|bm| bm.name.clone()).collect()
+    }
+
+    fn store() -> MemoryStore {
+        let store = MemoryStore::new();
+        for (name, tags, folder) in [("Rust", "rust", "Dev"), ("Tokio", "rust,async", "Dev/Async"), ("News", "", "")] {
+            store.add(&Bookmark {
+                name: name.to_string(),
+                url: format!("https://{}.example/", name.to_lowercase()),
+                tags: tags.split(',').filter(|t| !t.is_empty()).map(String::from).collect(),
+                folder: folder.to_string(),
+                ..Default::default()
+            }).unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn filters_by_sidebar_and_query() {
+        let store = store();
+        let mut app = App::new(&store).unwrap();
+
+        assert_eq!(app.scopes[1..], [
+            Scope::Tag("async".to_string()),
+            Scope::Tag("rust".to_string()),
+            Scope::Folder("Dev".to_string()),
+            Scope::Folder("Dev/Async".to_string()),
+        ]);
+
+        key(&mut app, KeyCode::Tab);
+        key(&mut app, KeyCode::End);
+        key(&mut app, KeyCode::Up);
+        assert_eq!(app.scope(), Scope::Folder("Dev".to_string()));
+        assert_eq!(names(&app).len(), 2);
+
+        key(&mut app, KeyCode::Char('/'));
+        for c in "tok".chars() {
+            key(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(names(&app), ["Tokio"]);
+    }
+
+    #[test]
+    fn deletes_and_tags_the_selected_bookmark() {
+        let store = store();
+        let mut app = App::new(&store).unwrap();
+        key(&mut app, KeyCode::Char('/'));
+        for c in "news".chars() {
+            key(&mut app, KeyCode::Char(c));
+        }
+        key(&mut app, KeyCode::Enter);
+
+        key(&mut app, KeyCode::Char('t'));
+        for c in "daily".chars() {
+            key(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(key(&mut app, KeyCode::Enter), Flow::Redraw);
+        assert_eq!(app.selected().unwrap().tags, ["daily"]);
+
+        key(&mut app, KeyCode::Char('d'));
+        key(&mut app, KeyCode::Char('n'));
+        assert_eq!(store.all().unwrap().len(), 3);
+        key(&mut app, KeyCode::Char('d'));
+        key(&mut app, KeyCode::Char('y'));
+        assert_eq!(store.all().unwrap().len(), 2);
+        assert!(app.selected().is_none());
+    }
+}
@@ -22,30 +22,13 @@
 */
 
 use std::{thread, time::Duration};
-use std::process::{self, Command};
-use std::error::Error;
+use std::process::Command;
 
-use arboard::Clipboard;
 use reqwest::blocking::get;
+use url::{Host, Url};
 
-pub fn die<E: Into<Box<dyn Error>>>(message: &str, error: E) -> ! {
-    eprintln!("\x1b[1;31mError:\x1b[0m {}: {}", message, error.into());
-    process::exit(1);
-}
-
-pub fn user_input_error() -> ! {
-    let err = std::io::Error::new(std::io::ErrorKind::InvalidInput, "Propmt failed!");
-    die("User input error", err);
-}
-
-pub fn sql_driver_error<E: Into<Box<dyn Error>>>(error: E) -> ! {
-    die("SQL Error", error);
-}
-
-pub fn parser_error<E: Into<Box<dyn Error>>>(error: E) -> ! {
-    die("Parser Error", error);
-
-}
+use crate::config;
+use crate::error::{BookmanError, Result};
 
 pub fn sleep(seconds: u64) {
     thread::sleep(Duration::from_secs(seconds));
@@ -59,76 +42,129 @@ pub fn is_installed(binary: &str) -> bool {
         .unwrap_or(false)
 }
 
-pub fn to_wl_copy(text: &str) { 
-    let mut daemon = Command::new("sh")
-                        .arg("-c")
-                        .arg(format!("echo '{}' | wl-copy", text))
-                        .spawn()
-                        .expect("Failed to copy. Is wl-copy installed?");
-    sleep(45);
-    let _ = daemon.kill();
-}
+pub fn prompt_user() -> Result<(String, String, String)> {
+    let mut url = String::new();
+    let mut name = String::new();
+    let mut description = String::new();
 
-fn to_clipboard(text: &str, wait: bool) {
-    let mut clipboard = Clipboard::new().unwrap();
-    clipboard.set_text(text).unwrap();
-    if wait {
-        sleep(45);
-    }
+    println!("Enter name:");
+    std::io::stdin().read_line(&mut name)?;
+
+    println!("Enter URL:");
+    std::io::stdin().read_line(&mut url)?;
+
+    println!("Enter description:");
+    std::io::stdin().read_line(&mut description)?;
+
+    Ok((name, url, description))
 }
 
-pub fn copy_to_clipboard(text: &str) {
-    #[cfg(target_os = "linux")]
-    {
-        if is_installed("wl-copy") {
-            to_wl_copy(text);
-        } else {
-            to_clipboard(text, true);
-        }
-    }
+pub fn confirm(question: &str) -> bool {
+    let mut answer = String::new();
 
-    #[cfg(not(target_os = "linux"))]
-    {
-        to_clipboard(text, false);
+    println!("{} [y/N]", question);
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
     }
 
-    #[cfg(debug_assertions)]
-    {
-        println!("Copied to clipboard: {}", text);
-    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+pub fn clean_tags(tags: &[String]) -> Vec<String> {
+    let mut cleaned: Vec<String> = tags.iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    cleaned.sort();
+    cleaned.dedup();
+    cleaned
 }
 
-pub fn copy_from_clipboard() -> String {
-    let mut clipboard = Clipboard::new().unwrap();
-    let url = clipboard.get_text().unwrap_or_else(|_| "".to_string());
+pub fn read_stdin_lines() -> Vec<String> {
+    std::io::stdin()
+        .lines()
+        .map_while(std::io::Result::ok)
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
 
-    #[cfg(debug_assertions)]
-    {
-        println!("Copied from clipboard: {}", url);
-    }
+pub fn fetch_page(url: &str) -> Result<(u16, String)> {
+    let response = get(url)?;
+    let status = response.status().as_u16();
 
-    return url;
+    Ok((status, response.text()?))
 }
 
-pub fn prompt_user() -> Option<(String, String, String)> {
-    let mut url = String::new();
-    let mut name = String::new();
-    let mut description = String::new();
+// A leading "word:" is a scheme unless it is followed by a port number (e.g. "localhost:8080").
+fn has_scheme(input: &str) -> bool {
+    match input.split_once(':') {
+        Some((scheme, rest)) => {
+            rest.starts_with("//")
+                || (scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+                    && !rest.starts_with(|c: char| c.is_ascii_digit()))
+        }
+        None => false,
+    }
+}
 
-    println!("Enter name:");
-    std::io::stdin().read_line(&mut name).unwrap();
+pub fn normalize_url(input: &str, force: bool) -> Result<String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(BookmanError::InvalidInput("URL is empty".to_string()));
+    }
+    if force {
+        return Ok(input.to_string());
+    }
 
-    println!("Enter URL:");
-    std::io::stdin().read_line(&mut url).unwrap();
+    let prefixed = !has_scheme(input);
+    let candidate = if prefixed { format!("https://{}", input) } else { input.to_string() };
 
-    println!("Enter description:");
-    std::io::stdin().read_line(&mut description).unwrap();
+    let url = Url::parse(&candidate)
+        .map_err(|err| BookmanError::InvalidInput(
+            format!("'{}' is not a valid URL ({}), use --force to store it anyway", input, err)))?;
+
+    if !config::URL_SCHEMES.contains(&url.scheme()) {
+        return Err(BookmanError::InvalidInput(
+            format!("scheme '{}' is not allowed in '{}', use --force to store it anyway", url.scheme(), input)));
+    }
 
-    return Some((name.clone(), url.clone(), description.clone()));
+    // A bare word like "notes" becomes "https://notes/", IP addresses (e.g. "[::1]:80") are fine.
+    if prefixed {
+        let single_word = match url.host() {
+            Some(Host::Domain(host)) => !host.contains('.') && host != "localhost",
+            Some(_) => false,
+            None => true,
+        };
+        if single_word {
+            return Err(BookmanError::InvalidInput(
+                format!("'{}' does not look like a URL, use --force to store it anyway", input)));
+        }
+    }
+
+    Ok(url.to_string())
 }
 
-pub fn fetch_page(url: &str) -> Result<String, Box<dyn Error>> {
-    let response = get(url)?.text()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_urls_without_scheme() {
+        assert_eq!(normalize_url("localhost:3000", false).unwrap(), "https://localhost:3000/");
+        assert_eq!(normalize_url("example.com:8080/x", false).unwrap(), "https://example.com:8080/x");
+        assert_eq!(normalize_url("[::1]:80", false).unwrap(), "https://[::1]:80/");
+        assert_eq!(normalize_url("mailto:a@b.c", false).unwrap(), "mailto:a@b.c");
+    }
 
-    Ok(response)
+    #[test]
+    fn rejects_what_does_not_look_like_a_url() {
+        assert!(matches!(normalize_url("javascript:alert(1)", false), Err(BookmanError::InvalidInput(_))));
+        assert!(matches!(normalize_url("notes", false), Err(BookmanError::InvalidInput(_))));
+        assert!(matches!(normalize_url("  ", true), Err(BookmanError::InvalidInput(_))));
+
+        assert_eq!(normalize_url("javascript:alert(1)", true).unwrap(), "javascript:alert(1)");
+        assert_eq!(normalize_url(" notes ", true).unwrap(), "notes");
+    }
 }
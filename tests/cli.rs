@@ -0,0 +1,130 @@
+/*
+    This file is a part of bookman software.
+
+    These tests run the bookman binary against a temporary encrypted database.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output};
+
+// A $HOME of its own for each test, removed when the test ends.
+struct Home(PathBuf);
+
+impl Home {
+    fn new(name: &str) -> Home {
+        let path = env::temp_dir().join(format!("bookman-cli-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Home(path)
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_bookman"))
+            .args(args)
+            .env("HOME", &self.0)
+            .env_remove("BROWSER")
+            // No desktop notifications while testing, they fall back to stderr.
+            .env("DBUS_SESSION_BUS_ADDRESS", "unix:path=/nonexistent")
+            .output()
+            .unwrap()
+    }
+
+    fn stdout(&self, args: &[&str]) -> String {
+        let output = self.run(args);
+        assert!(output.status.success(), "bookman {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    }
+}
+
+impl Drop for Home {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn fixture(name: &str) -> String {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name).to_str().unwrap().to_string()
+}
+
+#[test]
+fn imports_queries_and_undoes() {
+    let home = Home::new("import");
+    home.stdout(&["import", &fixture("firefox.html")]);
+
+    assert_eq!(home.stdout(&["list"]).lines().count(), 4);
+    assert_eq!(home.stdout(&["find", "tag:rust"]), "https://doc.rust-lang.org/book/\nhttps://tokio.rs/\n");
+    assert_eq!(home.stdout(&["list", "folder:Rust/Async"]), "3 | Tokio | https://tokio.rs/\n");
+
+    assert_eq!(home.stdout(&["undo"]), "4 changes reverted\n");
+    assert_eq!(home.stdout(&["list"]), "");
+}
+
+#[test]
+fn database_is_encrypted() {
+    let home = Home::new("encrypted");
+    home.stdout(&["import", &fixture("chrome.html")]);
+
+    let database = fs::read(home.0.join(".local/share/bookman/bookmarks.db")).unwrap();
+    assert!(!database.starts_with(b"SQLite format 3\0"));
+    assert!(!database.windows(6).any(|window| window == b"GitHub"));
+}
+
+#[test]
+fn json_exports_import_into_another_database() {
+    let home = Home::new("export");
+    home.stdout(&["import", &fixture("firefox.html")]);
+    let output = home.0.join("backup.json");
+    home.stdout(&["export", "--format", "json", "--output", output.to_str().unwrap()]);
+
+    let other = Home::new("export-other");
+    other.stdout(&["import", output.to_str().unwrap()]);
+
+    assert_eq!(other.stdout(&["list"]), home.stdout(&["list"]));
+    assert_eq!(other.stdout(&["find", "folder:\"Bookmarks Toolbar\""]), "https://news.ycombinator.com/\n");
+}
+
+#[test]
+fn notifications_fall_back_to_stderr() {
+    let home = Home::new("notify");
+    let output = home.run(&["add", "--no-fetch", "https://example.org/", "--name", "Example"]);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Bookman: Bookmark is saved!"));
+    assert_eq!(home.stdout(&["find", "example"]), "https://example.org/\n");
+}
+
+#[test]
+fn errors_have_distinct_exit_codes() {
+    let home = Home::new("errors");
+
+    assert_eq!(home.run(&["add", "--no-fetch", "not a url"]).status.code(), Some(2));
+    assert_eq!(home.run(&["list", "rating:9"]).status.code(), Some(3));
+    assert_eq!(home.run(&["remove", "--yes", "99"]).status.code(), Some(4));
+    assert_eq!(home.run(&["collection", "export", "missing"]).status.code(), Some(4));
+    assert_eq!(home.run(&["undo"]).status.code(), Some(4));
+
+    let json = home.0.join("rated.json");
+    fs::write(&json, r#"[{"url": "https://example.org/", "rating": 9}]"#).unwrap();
+    assert_eq!(home.run(&["import", json.to_str().unwrap()]).status.code(), Some(3));
+    fs::write(&json, r#"[{"url": "https://example.org/", "state": "lost"}]"#).unwrap();
+    assert_eq!(home.run(&["import", json.to_str().unwrap()]).status.code(), Some(3));
+}
@@ -0,0 +1,97 @@
+/*
+    This file is a part of bookman software.
+
+    These tests fetch fixture pages from a local HTTP server.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use bookman::{parser, utils, BookmanError};
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::thread;
+
+// Serves tests/fixtures until the test process exits and returns the base URL.
+fn serve_fixtures() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(&stream);
+            let mut request = String::new();
+            let _ = reader.read_line(&mut request);
+
+            // Skip the headers, the request has no body.
+            let mut header = String::new();
+            while reader.read_line(&mut header).is_ok_and(|n| n > 2) {
+                header.clear();
+            }
+
+            let path = request.split_whitespace().nth(1).unwrap_or("/").trim_start_matches('/');
+            let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(path);
+            let (status, body) = match fs::read_to_string(&file) {
+                Ok(body) => ("200 OK", body),
+                Err(_) => ("404 Not Found", "<html><head><title>Not found</title></head></html>".to_string()),
+            };
+
+            let _ = write!(&stream,
+                "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status, body.len(), body);
+        }
+    });
+
+    base
+}
+
+#[test]
+fn fetches_and_parses_a_page() {
+    let base = serve_fixtures();
+
+    let (status, text) = utils::fetch_page(&format!("{}/page.html", base)).unwrap();
+
+    assert_eq!(status, 200);
+    assert_eq!(parser::parse_html_text(&text).unwrap(), "Fixture page");
+    assert_eq!(parser::parse_html_description(&text).unwrap(), "A page served by the test server");
+    assert_eq!(parser::parse_html_snippet(&text, 300).unwrap(), "First paragraph. Second paragraph spanning two lines.");
+    assert_eq!(parser::parse_html_snippet(&text, 5).unwrap(), "First");
+}
+
+#[test]
+fn missing_pages_report_their_status() {
+    let base = serve_fixtures();
+
+    let (status, text) = utils::fetch_page(&format!("{}/missing.html", base)).unwrap();
+
+    assert_eq!(status, 404);
+    assert!(parser::parse_html_description(&text).is_err());
+}
+
+#[test]
+fn unreachable_hosts_are_network_errors() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+
+    let result = utils::fetch_page(&format!("http://{}/page.html", address));
+
+    assert!(matches!(result, Err(BookmanError::Network(_))));
+}
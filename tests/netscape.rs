@@ -0,0 +1,92 @@
+/*
+    This file is a part of bookman software.
+
+    These tests import and export the bookmark files of Firefox and Chrome.
+
+    Copyright (c) 2025 Pavel Pleskunov.
+
+    bookman is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 3 of the License, or (at
+    your option) any later version.
+
+    bookman is distributed in the hope that it will be useful, but
+    WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+    General Public License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with this program; if not, write to the Free Software
+    Foundation, Inc., 59 Temple Place, Suite 330, Boston, MA 02111-1307
+    USA
+*/
+
+use bookman::{export, parser, Bookmark};
+
+use std::path::Path;
+
+fn fixture(name: &str) -> Vec<Bookmark> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+
+    parser::parse_bookmarks(path.to_str().unwrap()).unwrap()
+}
+
+fn by_url(bookmarks: &[Bookmark], url: &str) -> Bookmark {
+    bookmarks.iter().find(|bm| bm.url == url).cloned().unwrap()
+}
+
+// Compares what a Netscape file can hold, the order changes because bookman groups by folder.
+fn assert_same(left: &[Bookmark], right: &[Bookmark]) {
+    assert_eq!(left.len(), right.len());
+
+    for expected in left {
+        let actual = by_url(right, &expected.url);
+        assert_eq!(actual.name, expected.name);
+        assert_eq!(actual.description, expected.description);
+        assert_eq!(actual.tags, expected.tags);
+        assert_eq!(actual.folder, expected.folder);
+        assert_eq!(actual.added, expected.added);
+    }
+}
+
+#[test]
+fn reads_firefox_exports() {
+    let bookmarks = fixture("firefox.html");
+    assert_eq!(bookmarks.len(), 4);
+
+    let book = by_url(&bookmarks, "https://doc.rust-lang.org/book/");
+    assert_eq!(book.name, "The Rust Programming Language");
+    assert_eq!(book.description, "An introductory book about Rust");
+    assert_eq!(book.tags, vec!["rust".to_string(), "book".to_string()]);
+    assert_eq!(book.folder, "Rust");
+    assert_eq!(book.added, Some(1700000100));
+
+    assert_eq!(by_url(&bookmarks, "https://tokio.rs/").folder, "Rust/Async");
+    assert_eq!(by_url(&bookmarks, "https://news.ycombinator.com/").folder, "Bookmarks Toolbar");
+    assert_eq!(by_url(&bookmarks, "https://www.mozilla.org/en-US/firefox/central/").folder, "");
+    assert_eq!(by_url(&bookmarks, "https://tokio.rs/").description, "");
+}
+
+#[test]
+fn reads_chrome_exports() {
+    let bookmarks = fixture("chrome.html");
+    assert_eq!(bookmarks.len(), 3);
+
+    let wiki = by_url(&bookmarks, "https://example.com/wiki?page=a&lang=en");
+    assert_eq!(wiki.name, "Team wiki & docs");
+    assert_eq!(wiki.folder, "Bookmarks bar/Work");
+    assert!(wiki.tags.is_empty());
+
+    assert_eq!(by_url(&bookmarks, "https://github.com/").folder, "Bookmarks bar");
+    assert_eq!(by_url(&bookmarks, "https://www.rust-lang.org/").added, Some(1700000050));
+}
+
+#[test]
+fn round_trips_browser_exports() {
+    for name in ["firefox.html", "chrome.html"] {
+        let imported = fixture(name);
+        let exported = export::to_html(&imported, "Bookmarks");
+
+        assert_same(&imported, &parser::parse_netscape(&exported));
+    }
+}